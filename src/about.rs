@@ -0,0 +1,361 @@
+use std::{collections::HashSet, fs, path::Path};
+
+use anyhow::{Result, anyhow, bail};
+use roxmltree::Document;
+
+use crate::xml::{XmlWriter, child, child_list, child_text, escape};
+
+pub const ABOUT_FILE_PATH: &str = "About/About.xml";
+
+const ROOT_TAG: &str = "ModMetaData";
+
+/// Typed view of `About/About.xml`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ModMetadata {
+    pub name: String,
+    pub authors: Vec<String>,
+    pub package_id: String,
+    pub mod_version: Option<String>,
+    pub supported_versions: Vec<String>,
+    pub description: String,
+    pub url: Option<String>,
+    pub mod_dependencies: Vec<ModDependency>,
+    pub load_before: Vec<String>,
+    pub load_after: Vec<String>,
+    pub incompatible_with: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ModDependency {
+    pub package_id: Option<String>,
+    pub display_name: Option<String>,
+    pub steam_workshop_url: Option<String>,
+    pub download_url: Option<String>,
+}
+
+impl ModMetadata {
+    pub fn load(mod_root: &Path) -> Result<Self> {
+        let path = mod_root.join(ABOUT_FILE_PATH);
+        let content = fs::read_to_string(&path)
+            .map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+        Self::from_xml(&content).map_err(|e| anyhow!("Failed to parse {}: {}", path.display(), e))
+    }
+
+    pub fn save(&self, mod_root: &Path) -> Result<()> {
+        let path = mod_root.join(ABOUT_FILE_PATH);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, self.to_xml())?;
+        Ok(())
+    }
+
+    pub fn from_xml(content: &str) -> Result<Self> {
+        let doc = Document::parse(content)?;
+        let root = doc.root_element();
+        if root.tag_name().name() != ROOT_TAG {
+            bail!(
+                "Expected root element <{}>, found <{}>",
+                ROOT_TAG,
+                root.tag_name().name()
+            );
+        }
+
+        let mut authors = child_list(root, "authors");
+        if let Some(author) = child_text(root, "author") {
+            // RimWorld accepts a comma separated list in the single form
+            authors.extend(
+                author
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .map(String::from),
+            );
+        }
+
+        let mod_dependencies = child(root, "modDependencies")
+            .map(|list| {
+                list.children()
+                    .filter(|n| n.is_element() && n.tag_name().name() == "li")
+                    .map(|li| ModDependency {
+                        package_id: child_text(li, "packageId"),
+                        display_name: child_text(li, "displayName"),
+                        steam_workshop_url: child_text(li, "steamWorkshopUrl"),
+                        download_url: child_text(li, "downloadUrl"),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(ModMetadata {
+            name: child_text(root, "name").unwrap_or_default(),
+            authors,
+            package_id: child_text(root, "packageId").unwrap_or_default(),
+            mod_version: child_text(root, "modVersion"),
+            supported_versions: child_list(root, "supportedVersions"),
+            description: child_text(root, "description").unwrap_or_default(),
+            url: child_text(root, "url"),
+            mod_dependencies,
+            load_before: child_list(root, "loadBefore"),
+            load_after: child_list(root, "loadAfter"),
+            incompatible_with: child_list(root, "incompatibleWith"),
+        })
+    }
+
+    pub fn to_xml(&self) -> String {
+        let mut w = XmlWriter::new();
+        w.open(ROOT_TAG).elem("name", &self.name);
+        match self.authors.as_slice() {
+            [] => {},
+            [author] => {
+                w.elem("author", author);
+            },
+            authors => {
+                w.list("authors", authors);
+            },
+        }
+        w.elem("packageId", &self.package_id)
+            .elem_opt("modVersion", self.mod_version.as_deref())
+            .list("supportedVersions", &self.supported_versions)
+            .elem("description", &self.description)
+            .elem_opt("url", self.url.as_deref());
+
+        if !self.mod_dependencies.is_empty() {
+            w.open("modDependencies");
+            for dep in &self.mod_dependencies {
+                w.open("li")
+                    .elem_opt("packageId", dep.package_id.as_deref())
+                    .elem_opt("displayName", dep.display_name.as_deref())
+                    .elem_opt("steamWorkshopUrl", dep.steam_workshop_url.as_deref())
+                    .elem_opt("downloadUrl", dep.download_url.as_deref())
+                    .close("li");
+            }
+            w.close("modDependencies");
+        }
+
+        w.list("loadBefore", &self.load_before)
+            .list("loadAfter", &self.load_after)
+            .list("incompatibleWith", &self.incompatible_with)
+            .close(ROOT_TAG);
+        w.finish()
+    }
+}
+
+impl ModMetadata {
+    /// Problems that would make RimWorld reject or misload the mod.
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        if self.name.is_empty() {
+            problems.push("Missing <name>".to_string());
+        }
+        if self.package_id.is_empty() {
+            problems.push("Missing <packageId>".to_string());
+        } else if !is_valid_package_id(&self.package_id) {
+            problems.push(format!(
+                "Malformed <packageId> '{}', expected 'Author.ModName' with only letters, digits, '_' and '.'",
+                self.package_id
+            ));
+        }
+        if self.supported_versions.is_empty() {
+            problems.push("Empty <supportedVersions>".to_string());
+        }
+
+        for (tag, items) in [
+            ("authors", &self.authors),
+            ("supportedVersions", &self.supported_versions),
+            ("loadBefore", &self.load_before),
+            ("loadAfter", &self.load_after),
+            ("incompatibleWith", &self.incompatible_with),
+        ] {
+            for dup in duplicates(items.iter().map(String::as_str)) {
+                problems.push(format!("Duplicate entry '{}' in <{}>", dup, tag));
+            }
+        }
+
+        for (i, dep) in self.mod_dependencies.iter().enumerate() {
+            match dep.package_id.as_deref() {
+                None | Some("") => problems.push(format!(
+                    "<modDependencies> entry {} ({}) lacks <packageId>",
+                    i + 1,
+                    dep.display_name.as_deref().unwrap_or("unnamed")
+                )),
+                Some(id) if !is_valid_package_id(id) => problems.push(format!(
+                    "Malformed <packageId> '{}' in <modDependencies>",
+                    id
+                )),
+                _ => {},
+            }
+        }
+        for dup in duplicates(
+            self.mod_dependencies
+                .iter()
+                .filter_map(|dep| dep.package_id.as_deref()),
+        ) {
+            problems.push(format!("Duplicate entry '{}' in <modDependencies>", dup));
+        }
+
+        problems
+    }
+}
+
+/// Sets `<modVersion>` in the text of an About.xml, leaving everything else,
+/// including elements [`ModMetadata`] does not model, untouched.
+pub fn set_mod_version(content: &str, version: &str) -> Result<String> {
+    let doc = Document::parse(content)?;
+    let root = doc.root_element();
+    let elem = format!("<modVersion>{}</modVersion>", escape(version));
+
+    if let Some(node) = child(root, "modVersion") {
+        let range = node.range();
+        return Ok(format!(
+            "{}{}{}",
+            &content[..range.start],
+            elem,
+            &content[range.end..]
+        ));
+    }
+
+    // Place it after <packageId> with the same indentation, or last
+    let node = child(root, "packageId")
+        .or_else(|| root.children().rfind(|n| n.is_element()))
+        .ok_or_else(|| anyhow!("Empty <{}>", ROOT_TAG))?;
+    let (anchor, at) = (node.range().start, node.range().end);
+    let line_start = content[..anchor].rfind('\n').map_or(0, |i| i + 1);
+    let indent = &content[line_start..anchor];
+    let indent = if indent.trim().is_empty() {
+        indent
+    } else {
+        "\t"
+    };
+    Ok(format!(
+        "{}\n{}{}{}",
+        &content[..at],
+        indent,
+        elem,
+        &content[at..]
+    ))
+}
+
+pub fn is_valid_package_id(id: &str) -> bool {
+    id.contains('.')
+        && id.split('.').all(|seg| {
+            !seg.is_empty() && seg.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        })
+}
+
+/// Entries appearing more than once, compared case-insensitively like RimWorld
+/// does for package ids.
+fn duplicates<'a>(items: impl Iterator<Item = &'a str>) -> Vec<&'a str> {
+    let mut seen = HashSet::new();
+    let mut dups = Vec::new();
+    for item in items {
+        if !seen.insert(item.to_lowercase()) && !dups.contains(&item) {
+            dups.push(item);
+        }
+    }
+    dups
+}
+
+/// Builds an `Author.ModName` style package id, dropping characters RimWorld
+/// does not accept.
+pub fn make_package_id(author: &str, name: &str) -> String {
+    let clean = |s: &str| {
+        s.chars()
+            .filter(|c| c.is_ascii_alphanumeric() || *c == '_')
+            .collect::<String>()
+    };
+    format!("{}.{}", clean(author), clean(name))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_about_round_trip() {
+        let meta = ModMetadata {
+            name: "My Mod".into(),
+            authors: vec!["Rhynia".into(), "Someone & Co".into()],
+            package_id: "Rhynia.MyMod".into(),
+            mod_version: Some("1.2.0".into()),
+            supported_versions: vec!["1.5".into(), "1.6".into()],
+            description: "Does <things>".into(),
+            url: None,
+            mod_dependencies: vec![ModDependency {
+                package_id: Some("brrainz.harmony".into()),
+                display_name: Some("Harmony".into()),
+                ..Default::default()
+            }],
+            load_after: vec!["brrainz.harmony".into()],
+            ..Default::default()
+        };
+        assert_eq!(ModMetadata::from_xml(&meta.to_xml()).unwrap(), meta);
+    }
+
+    #[test]
+    fn test_about_single_author() {
+        let meta = ModMetadata::from_xml(
+            "<ModMetaData><name>A</name><author>X, Y</author><packageId>X.A</packageId></ModMetaData>",
+        )
+        .unwrap();
+        assert_eq!(meta.authors, vec!["X", "Y"]);
+        assert!(ModMetadata::from_xml("<Defs/>").is_err());
+    }
+
+    #[test]
+    fn test_about_validate() {
+        let meta = ModMetadata::from_xml(
+            r#"<ModMetaData>
+                <name>A</name>
+                <packageId>Rhynia.My Mod</packageId>
+                <supportedVersions><li>1.5</li><li>1.5</li></supportedVersions>
+                <modDependencies><li><displayName>Harmony</displayName></li></modDependencies>
+            </ModMetaData>"#,
+        )
+        .unwrap();
+        let problems = meta.validate();
+        assert_eq!(problems.len(), 3, "{:?}", problems);
+
+        let meta = ModMetadata {
+            name: "A".into(),
+            package_id: "Rhynia.A".into(),
+            supported_versions: vec!["1.6".into()],
+            ..Default::default()
+        };
+        assert!(meta.validate().is_empty());
+        assert!(!is_valid_package_id("NoDot"));
+        assert!(!is_valid_package_id("Trailing."));
+    }
+
+    #[test]
+    fn test_set_mod_version() {
+        let content = "<ModMetaData>\n\t<name>A</name>\n\t<packageId>X.A</packageId>\n\t<modIconPath>Icon</modIconPath>\n</ModMetaData>\n";
+        let updated = set_mod_version(content, "1.0.0").unwrap();
+        assert_eq!(
+            updated,
+            "<ModMetaData>\n\t<name>A</name>\n\t<packageId>X.A</packageId>\n\t<modVersion>1.0.0</modVersion>\n\t<modIconPath>Icon</modIconPath>\n</ModMetaData>\n"
+        );
+        let updated = set_mod_version(&updated, "1.1.0").unwrap();
+        assert_eq!(
+            ModMetadata::from_xml(&updated)
+                .unwrap()
+                .mod_version
+                .as_deref(),
+            Some("1.1.0")
+        );
+        assert_eq!(updated.matches("<modVersion>").count(), 1);
+
+        let updated =
+            set_mod_version("<ModMetaData>\n  <name>A</name>\n</ModMetaData>", "2.0.0").unwrap();
+        assert_eq!(
+            updated,
+            "<ModMetaData>\n  <name>A</name>\n  <modVersion>2.0.0</modVersion>\n</ModMetaData>"
+        );
+    }
+
+    #[test]
+    fn test_make_package_id() {
+        assert_eq!(make_package_id("Rhy Vis", "My Mod!"), "RhyVis.MyMod");
+    }
+}
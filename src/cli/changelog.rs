@@ -0,0 +1,441 @@
+use std::{fs, path::Path, process::Command};
+
+use anyhow::{Result, anyhow};
+use clap::Args;
+use log::{debug, info, warn};
+
+use super::ProjectConf;
+
+pub const CHANGELOG_FILE_NAME: &str = "CHANGELOG.md";
+/// Plain-text notes of the latest release, picked up by `workshop upload`
+pub const CHANGENOTE_FILE_NAME: &str = ".rimpub.changenote";
+
+const CHANGELOG_HEADER: &str = "# Changelog";
+const UNRELEASED: &str = "Unreleased";
+const OTHER_GROUP: &str = "Other Changes";
+
+/// Conventional commit types and their section titles, in output order.
+/// Other types go under [`OTHER_GROUP`] unless they are [`SKIPPED_TYPES`].
+const GROUPS: &[(&str, &str)] = &[
+    ("feat", "Features"),
+    ("fix", "Bug Fixes"),
+    ("perf", "Performance"),
+    ("refactor", "Refactoring"),
+    ("docs", "Documentation"),
+];
+/// Maintenance types players do not care about
+const SKIPPED_TYPES: &[&str] = &["chore", "ci", "build", "test", "style"];
+
+#[derive(Debug, Args)]
+pub struct ChangelogArgs {
+    /// Collect commits after this ref instead of the last version tag
+    #[arg(long, value_name = "REF")]
+    pub since: Option<String>,
+
+    /// Version to title the section with, defaults to the project version
+    #[arg(long, value_name = "VERSION")]
+    pub release: Option<String>,
+
+    /// Print the changelog section without writing any file
+    #[arg(short = 'n', long)]
+    pub dry_run: bool,
+}
+
+/// A commit subject split by the conventional commit format, e.g.
+/// `feat(publish)!: add links`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Commit {
+    pub hash: String,
+    pub kind: Option<String>,
+    pub scope: Option<String>,
+    pub breaking: bool,
+    pub description: String,
+}
+
+impl Commit {
+    pub fn parse(hash: &str, subject: &str) -> Self {
+        let conventional = subject.split_once(": ").and_then(|(head, description)| {
+            let (head, breaking) = match head.strip_suffix('!') {
+                Some(head) => (head, true),
+                None => (head, false),
+            };
+            let (kind, scope) = match head.split_once('(') {
+                Some((kind, scope)) => (kind, Some(scope.strip_suffix(')')?.to_string())),
+                None => (head, None),
+            };
+            if kind.is_empty() || !kind.chars().all(|c| c.is_ascii_alphabetic()) {
+                return None;
+            }
+            Some(Commit {
+                hash: hash.to_string(),
+                kind: Some(kind.to_ascii_lowercase()),
+                scope,
+                breaking,
+                description: description.trim().to_string(),
+            })
+        });
+
+        conventional.unwrap_or_else(|| Commit {
+            hash: hash.to_string(),
+            kind: None,
+            scope: None,
+            breaking: false,
+            description: subject.trim().to_string(),
+        })
+    }
+
+    fn group(&self) -> Option<&'static str> {
+        match self.kind.as_deref() {
+            Some(kind) if SKIPPED_TYPES.contains(&kind) => None,
+            Some(kind) => Some(
+                GROUPS
+                    .iter()
+                    .find(|(k, _)| *k == kind)
+                    .map_or(OTHER_GROUP, |(_, title)| *title),
+            ),
+            None => Some(OTHER_GROUP),
+        }
+    }
+}
+
+/// Commits grouped into changelog sections, breaking changes first.
+#[derive(Debug, Default)]
+pub struct Release {
+    pub breaking: Vec<Commit>,
+    pub groups: Vec<(&'static str, Vec<Commit>)>,
+}
+
+impl Release {
+    pub fn new(commits: Vec<Commit>) -> Self {
+        let mut release = Release::default();
+        for commit in commits {
+            if commit.breaking {
+                release.breaking.push(commit);
+                continue;
+            }
+            let Some(title) = commit.group() else {
+                debug!("Leaving out commit: {}", commit.description);
+                continue;
+            };
+            match release.groups.iter_mut().find(|(t, _)| *t == title) {
+                Some((_, commits)) => commits.push(commit),
+                None => release.groups.push((title, vec![commit])),
+            }
+        }
+        release.groups.sort_by_key(|(title, _)| {
+            GROUPS
+                .iter()
+                .position(|(_, t)| t == title)
+                .unwrap_or(GROUPS.len())
+        });
+        release
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.breaking.is_empty() && self.groups.is_empty()
+    }
+
+    fn sections(&self) -> impl Iterator<Item = (&str, &[Commit])> {
+        (!self.breaking.is_empty())
+            .then_some(("Breaking Changes", self.breaking.as_slice()))
+            .into_iter()
+            .chain(
+                self.groups
+                    .iter()
+                    .map(|(title, commits)| (*title, commits.as_slice())),
+            )
+    }
+
+    pub fn to_markdown(&self, heading: &str) -> String {
+        let mut out = format!("## {}\n", heading);
+        for (title, commits) in self.sections() {
+            out.push_str(&format!("\n### {}\n\n", title));
+            for commit in commits {
+                let scope = commit
+                    .scope
+                    .as_deref()
+                    .map(|scope| format!("**{}:** ", scope))
+                    .unwrap_or_default();
+                out.push_str(&format!(
+                    "- {}{} ({})\n",
+                    scope,
+                    commit.description,
+                    short_hash(&commit.hash)
+                ));
+            }
+        }
+        out
+    }
+
+    pub fn to_changenote(&self, heading: &str) -> String {
+        let mut out = format!("{}\n", heading);
+        for (title, commits) in self.sections() {
+            out.push_str(&format!("\n{}\n", title));
+            for commit in commits {
+                out.push_str(&format!("- {}\n", commit.description));
+            }
+        }
+        out
+    }
+}
+
+impl ChangelogArgs {
+    pub fn run(&self) -> Result<()> {
+        let working_directory = std::env::current_dir()?;
+        let (config, _) = ProjectConf::load_current()?;
+
+        let since = match &self.since {
+            Some(since) => Some(since.clone()),
+            None => last_version_tag(&working_directory)?,
+        };
+        match &since {
+            Some(since) => info!("Collecting commits since {}", since),
+            None => info!("No version tag found, collecting all commits"),
+        }
+
+        let release = Release::new(collect_commits(&working_directory, since.as_deref())?);
+        if release.is_empty() {
+            warn!("No changes to write");
+            return Ok(());
+        }
+
+        let version = self
+            .release
+            .clone()
+            .or_else(|| config.resolve_version(&working_directory))
+            .unwrap_or_else(|| UNRELEASED.to_string());
+        let date = git(&working_directory, &["log", "-1", "--format=%cs"])?;
+        let heading = format!("{} - {}", version, date.trim());
+        let section = release.to_markdown(&heading);
+
+        if self.dry_run {
+            info!("Changelog section:\n{}", section);
+            return Ok(());
+        }
+
+        let changelog_path = working_directory.join(CHANGELOG_FILE_NAME);
+        let existing = fs::read_to_string(&changelog_path).unwrap_or_default();
+        if version == UNRELEASED
+            && let Some(updated) = replace_section(&existing, UNRELEASED, &section)
+        {
+            // Commits since the last tag are collected again, so the new
+            // section holds the old one's entries too
+            fs::write(&changelog_path, updated)?;
+            info!(
+                "Updated the {} section of {}",
+                UNRELEASED, CHANGELOG_FILE_NAME
+            );
+        } else if existing
+            .lines()
+            .any(|line| line.starts_with(&format!("## {} ", version)))
+        {
+            warn!(
+                "{} already has a section for {}, leaving it untouched",
+                CHANGELOG_FILE_NAME, version
+            );
+        } else {
+            fs::write(&changelog_path, prepend_section(&existing, &section))?;
+            info!("Updated {}", CHANGELOG_FILE_NAME);
+        }
+
+        fs::write(
+            working_directory.join(CHANGENOTE_FILE_NAME),
+            release.to_changenote(&version),
+        )?;
+        info!("Workshop change note written to {}", CHANGENOTE_FILE_NAME);
+        Ok(())
+    }
+}
+
+/// Inserts `section` above the newest entry, below the file's title.
+fn prepend_section(existing: &str, section: &str) -> String {
+    let body = existing
+        .strip_prefix(CHANGELOG_HEADER)
+        .map(|rest| rest.trim_start_matches(['\r', '\n']))
+        .unwrap_or(existing);
+    if body.trim().is_empty() {
+        format!("{}\n\n{}", CHANGELOG_HEADER, section)
+    } else {
+        format!("{}\n\n{}\n{}", CHANGELOG_HEADER, section, body)
+    }
+}
+
+/// Replaces the section titled `version` with `section`, or `None` if there is
+/// no such section.
+fn replace_section(existing: &str, version: &str, section: &str) -> Option<String> {
+    let heading = format!("## {} ", version);
+    let start = line_starts(existing).find(|&i| existing[i..].starts_with(&heading))?;
+    let end = line_starts(existing)
+        .filter(|&i| i > start)
+        .find(|&i| existing[i..].starts_with("## "))
+        .unwrap_or(existing.len());
+    let separator = if end < existing.len() { "\n" } else { "" };
+    Some(format!(
+        "{}{}{}{}",
+        &existing[..start],
+        section,
+        separator,
+        &existing[end..]
+    ))
+}
+
+/// Byte offsets at which the lines of `text` start.
+fn line_starts(text: &str) -> impl Iterator<Item = usize> + '_ {
+    std::iter::once(0).chain(text.match_indices('\n').map(|(i, _)| i + 1))
+}
+
+fn last_version_tag(repo: &Path) -> Result<Option<String>> {
+    let output = Command::new("git")
+        .args(["describe", "--tags", "--abbrev=0"])
+        .args(["--match", "v[0-9]*", "--match", "[0-9]*"])
+        .current_dir(repo)
+        .output()
+        .map_err(|e| anyhow!("Failed to execute git: {}", e))?;
+    // Fails when there is no matching tag at all
+    Ok(output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .filter(|tag| !tag.is_empty()))
+}
+
+/// Non-merge commits after `since`, newest first.
+fn collect_commits(repo: &Path, since: Option<&str>) -> Result<Vec<Commit>> {
+    let range = since.map_or_else(|| "HEAD".to_string(), |since| format!("{}..HEAD", since));
+    let log = git(repo, &["log", "--no-merges", "--format=%H%x1f%s", &range])?;
+    Ok(log
+        .lines()
+        .filter_map(|line| line.split_once('\x1f'))
+        .map(|(hash, subject)| Commit::parse(hash, subject))
+        .collect())
+}
+
+fn git(repo: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(repo)
+        .output()
+        .map_err(|e| anyhow!("Failed to execute git: {}", e))?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+fn short_hash(hash: &str) -> &str {
+    &hash[..hash.len().min(7)]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_commit() {
+        let commit = Commit::parse("abc", "feat(publish)!: Add links");
+        assert_eq!(commit.kind.as_deref(), Some("feat"));
+        assert_eq!(commit.scope.as_deref(), Some("publish"));
+        assert!(commit.breaking);
+        assert_eq!(commit.description, "Add links");
+
+        let commit = Commit::parse("abc", "Fix typo: in readme");
+        assert_eq!(commit.kind, None);
+        assert_eq!(commit.description, "Fix typo: in readme");
+    }
+
+    #[test]
+    fn test_changelog_from_repo() {
+        let root = tempfile::tempdir().unwrap();
+        let repo = root.path();
+        let run = |args: &[&str]| {
+            git(
+                repo,
+                &[
+                    &["-c", "user.name=Test", "-c", "user.email=test@example.com"],
+                    &["-c", "commit.gpgsign=false", "-c", "tag.gpgsign=false"],
+                    args,
+                ]
+                .concat(),
+            )
+            .unwrap();
+        };
+        run(&["init", "-q"]);
+        run(&["commit", "-q", "--allow-empty", "-m", "feat: Old feature"]);
+        run(&["tag", "v1.0.0"]);
+        run(&[
+            "commit",
+            "-q",
+            "--allow-empty",
+            "-m",
+            "fix(defs): Broken recipe",
+        ]);
+        run(&["commit", "-q", "--allow-empty", "-m", "chore: Bump deps"]);
+        run(&["commit", "-q", "--allow-empty", "-m", "feat!: New weapons"]);
+        run(&["commit", "-q", "--allow-empty", "-m", "feat: Armor"]);
+        run(&["commit", "-q", "--allow-empty", "-m", "Tweak textures"]);
+
+        let since = last_version_tag(repo).unwrap();
+        assert_eq!(since.as_deref(), Some("v1.0.0"));
+        let release = Release::new(collect_commits(repo, since.as_deref()).unwrap());
+        let titles = release
+            .sections()
+            .map(|(title, commits)| (title, commits.len()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            titles,
+            vec![
+                ("Breaking Changes", 1),
+                ("Features", 1),
+                ("Bug Fixes", 1),
+                (OTHER_GROUP, 1)
+            ]
+        );
+        assert_eq!(
+            release.to_changenote("1.1.0"),
+            "1.1.0\n\nBreaking Changes\n- New weapons\n\nFeatures\n- Armor\n\nBug Fixes\n- Broken recipe\n\nOther Changes\n- Tweak textures\n"
+        );
+        assert!(
+            release
+                .to_markdown("1.1.0")
+                .contains("### Bug Fixes\n\n- **defs:** Broken recipe (")
+        );
+    }
+
+    #[test]
+    fn test_prepend_section() {
+        let first = prepend_section("", "## 1.0.0\n");
+        assert_eq!(first, "# Changelog\n\n## 1.0.0\n");
+        assert_eq!(
+            prepend_section(&first, "## 1.1.0\n"),
+            "# Changelog\n\n## 1.1.0\n\n## 1.0.0\n"
+        );
+    }
+
+    #[test]
+    fn test_replace_section() {
+        let existing = "# Changelog\n\n## Unreleased - 2024-01-01\n\n- Old\n\n## 1.0.0 - 2023-12-01\n\n- First\n";
+        assert_eq!(
+            replace_section(
+                existing,
+                UNRELEASED,
+                "## Unreleased - 2024-02-01\n\n- Old\n- New\n"
+            )
+            .unwrap(),
+            "# Changelog\n\n## Unreleased - 2024-02-01\n\n- Old\n- New\n\n## 1.0.0 - 2023-12-01\n\n- First\n"
+        );
+        assert_eq!(
+            replace_section(
+                "## Unreleased - 2024-01-01\n\n- Old\n",
+                UNRELEASED,
+                "## Unreleased - x\n"
+            )
+            .unwrap(),
+            "## Unreleased - x\n"
+        );
+        assert_eq!(replace_section(existing, "1.1.0", "## 1.1.0\n"), None);
+    }
+}
@@ -0,0 +1,231 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Result, anyhow};
+use clap::Args;
+use log::{debug, error, info, warn};
+
+use super::{Config, ProjectConf, SourceTree, publish::collect_source};
+use crate::{
+    about::ModMetadata,
+    lint::{
+        DefIndex, DefsDocument, Problem, check_matches, check_patches, check_xml_files,
+        collect_patches, def_files,
+    },
+    load_folders::LoadScopes,
+    util::RIMWORLD_APP_ID,
+};
+
+const DATA_PATH: &str = "Data";
+const CORE_FOLDER_NAME: &str = "Core";
+
+#[derive(Debug, Args)]
+pub struct CheckArgs {
+    /// Only check the folders the given game version loads, e.g. '1.5'
+    #[arg(long, value_name = "VERSION")]
+    pub game_version: Option<String>,
+
+    /// Also apply patch xpaths to the defs of Core, the mod and its
+    /// dependencies, reporting the ones that match nothing
+    #[arg(long)]
+    pub match_patches: bool,
+}
+
+impl CheckArgs {
+    pub fn run(&self) -> Result<()> {
+        let working_directory = std::env::current_dir()?;
+        let (mut config, _) = ProjectConf::load_current()?;
+        config.resolve_name();
+        info!("Checking project: {}", config.name);
+
+        let (source, _) =
+            collect_source(&working_directory, &config, self.game_version.as_deref())?;
+        let dependencies = Config::get_clone()
+            .path_game
+            .and_then(|path_game| find_dependencies(&path_game, &source.root, &config));
+        if dependencies.is_none() {
+            info!(
+                "Game data not found, only checking for duplicate defs; set 'path_game' to also check parents and references"
+            );
+        }
+        let mut problems = check_source(&source, dependencies.as_deref());
+        if self.match_patches {
+            match &dependencies {
+                Some(roots) => {
+                    let operations = collect_patches(&source.root, &source.files);
+                    let defs = load_defs_document(&source, roots);
+                    problems.extend(check_matches(&operations, &defs));
+                    sort_problems(&mut problems);
+                },
+                None => warn!("Game data not found, cannot match patches against Core defs"),
+            }
+        }
+        let errors = report(&problems);
+        if errors > 0 {
+            return Err(anyhow!("Found {} error(s)", errors));
+        }
+        info!("No errors found, {} warning(s)", problems.len() - errors);
+        Ok(())
+    }
+}
+
+/// Checks the files that would be published, stopping before a broken mod
+/// reaches the game. Only the mod itself is read, resolving parents and
+/// references against the game is left to `rimpub check`.
+pub(super) fn validate_source(source: &SourceTree) -> Result<()> {
+    let errors = report(&check_source(source, None));
+    if errors > 0 {
+        return Err(anyhow!(
+            "Found {} error(s) in mod files, fix them or pass '--no-validate'",
+            errors
+        ));
+    }
+    Ok(())
+}
+
+/// Checks the mod files, resolving parents and references against the mods
+/// in `dependencies` when given.
+fn check_source(source: &SourceTree, dependencies: Option<&[PathBuf]>) -> Vec<Problem> {
+    let mut problems = check_xml_files(&source.root, &source.files);
+
+    let index = DefIndex::collect(&source.root, &source.files);
+    debug!("Indexed {} def(s)", index.defs.len());
+    let operations = collect_patches(&source.root, &source.files);
+    debug!("Found {} patch operation(s)", operations.len());
+    problems.extend(check_patches(&operations));
+
+    let scopes = LoadScopes::new(&source.root).unwrap_or_else(|e| {
+        warn!(
+            "Failed to read load folders, checking every folder together: {}",
+            e
+        );
+        LoadScopes::default()
+    });
+    let external = dependencies.map(|roots| {
+        let mut external = DefIndex::default();
+        for root in roots {
+            external.extend(DefIndex::load_mod(root));
+        }
+        external
+    });
+    problems.extend(index.check(&scopes, external.as_ref()));
+
+    sort_problems(&mut problems);
+    problems
+}
+
+fn sort_problems(problems: &mut [Problem]) {
+    problems.sort_by(|a, b| (&a.path, a.line, a.column).cmp(&(&b.path, b.line, b.column)));
+}
+
+/// Folders of Core, every installed DLC and every dependency that can be
+/// found, or `None` without a game install.
+fn find_dependencies(
+    path_game: &Path,
+    working_directory: &Path,
+    config: &ProjectConf,
+) -> Option<Vec<PathBuf>> {
+    let data = path_game.join(DATA_PATH);
+    let core = data.join(CORE_FOLDER_NAME);
+    if !core.is_dir() {
+        warn!("{} not found, check 'path_game'", core.display());
+        return None;
+    }
+    // Core first, then the DLCs, which may use its defs as parents
+    let mut dlcs = fs::read_dir(&data)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .map(|entry| entry.path())
+                .filter(|path| path.is_dir() && *path != core)
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    dlcs.sort();
+    let mut roots = vec![core];
+    roots.extend(dlcs);
+
+    let package_ids = ModMetadata::load(working_directory)
+        .map(|meta| meta.mod_dependencies)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|dep| dep.package_id);
+    let search_dirs = [
+        path_game.join("Data"),
+        path_game.join("Mods"),
+        // Workshop items live next to the game in the same Steam library
+        path_game
+            .join("../../workshop/content")
+            .join(RIMWORLD_APP_ID),
+    ];
+    let mut installed = None;
+    for dependency in config.check.dependencies.iter().cloned().chain(package_ids) {
+        let folder = working_directory.join(&dependency);
+        let mod_root = if folder.is_dir() {
+            Some(folder)
+        } else {
+            let installed = installed.get_or_insert_with(|| find_installed_mods(&search_dirs));
+            installed
+                .iter()
+                .find(|(id, _)| id.eq_ignore_ascii_case(&dependency))
+                .map(|(_, root)| root.clone())
+        };
+        match mod_root {
+            // DLCs listed as dependencies are already indexed
+            Some(mod_root) if roots.contains(&mod_root) => {},
+            Some(mod_root) => {
+                debug!("Found {} at {}", dependency, mod_root.display());
+                roots.push(mod_root);
+            },
+            None => warn!(
+                "Dependency '{}' not found, references to its defs are reported as undefined",
+                dependency
+            ),
+        }
+    }
+    Some(roots)
+}
+
+/// The defs of every mod in `roots` merged with the project's own, as the
+/// game sees them before patching.
+fn load_defs_document(source: &SourceTree, roots: &[PathBuf]) -> DefsDocument {
+    let mut defs = DefsDocument::new();
+    let files = roots
+        .iter()
+        .flat_map(|root| def_files(root).into_iter().map(move |file| root.join(file)))
+        .chain(source.files.iter().map(|file| source.root.join(file)));
+    for file in files {
+        if let Ok(text) = fs::read_to_string(&file) {
+            defs.add_file(&text);
+        }
+    }
+    defs
+}
+
+/// Package ids and folders of the mods directly under `dirs`.
+fn find_installed_mods(dirs: &[PathBuf]) -> Vec<(String, PathBuf)> {
+    dirs.iter()
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flatten()
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter_map(|root| {
+            let meta = ModMetadata::load(&root).ok()?;
+            Some((meta.package_id, root))
+        })
+        .collect()
+}
+
+/// Logs `problems`, returning how many of them are errors.
+fn report(problems: &[Problem]) -> usize {
+    for problem in problems {
+        if problem.is_error() {
+            error!("{}", problem);
+        } else {
+            warn!("{}", problem);
+        }
+    }
+    problems.iter().filter(|p| p.is_error()).count()
+}
@@ -1,4 +1,5 @@
 use std::{
+    collections::BTreeMap,
     fs,
     io::ErrorKind,
     path::{Path, PathBuf},
@@ -10,7 +11,7 @@ use clap::{Args, Subcommand};
 use log::{debug, error, info, warn};
 use serde::{Deserialize, Serialize};
 
-use crate::util::{get_dir, read_steam_install_path};
+use crate::util::{RIMWORLD_APP_ID, find_steam_app_dir, get_dir, read_steam_install_path};
 
 #[derive(Debug, Args)]
 pub struct ConfigArgs {
@@ -50,6 +51,29 @@ pub struct Config {
 
     #[serde(default)]
     pub no_ask: bool,
+
+    /// steamcmd binary used for Workshop uploads, looked up in `PATH` if unset
+    #[serde(default)]
+    pub path_steamcmd: Option<PathBuf>,
+
+    /// Steam account steamcmd logs in with
+    #[serde(default)]
+    pub steam_user: Option<String>,
+
+    /// Named game installs to publish to, e.g. `[targets.unstable]`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub targets: BTreeMap<String, TargetConf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TargetConf {
+    pub path_game: PathBuf,
+}
+
+impl TargetConf {
+    pub fn get_path_mods(&self) -> PathBuf {
+        self.path_game.join(PATH_SEG_MODS)
+    }
 }
 
 static CONFIG: OnceLock<RwLock<Config>> = OnceLock::new();
@@ -57,6 +81,8 @@ static CONFIG: OnceLock<RwLock<Config>> = OnceLock::new();
 const CONFIG_FILE_NAME: &str = "Config.toml";
 const FIELD_PATH_GAME: &str = "path_game";
 const FIELD_NO_ASK: &str = "no_ask";
+const FIELD_PATH_STEAMCMD: &str = "path_steamcmd";
+const FIELD_STEAM_USER: &str = "steam_user";
 
 const PATH_SEG_RIMWORLD: &str = "steamapps/common/RimWorld";
 const PATH_SEG_MODS: &str = "Mods";
@@ -84,6 +110,18 @@ impl Config {
                     None
                 }),
             FIELD_NO_ASK => Some(config.no_ask.to_string()),
+            FIELD_PATH_STEAMCMD => config
+                .path_steamcmd
+                .clone()
+                .map(|p| p.to_string_lossy().to_string())
+                .or_else(|| {
+                    warn!("'{FIELD_PATH_STEAMCMD}' not set");
+                    None
+                }),
+            FIELD_STEAM_USER => config.steam_user.clone().or_else(|| {
+                warn!("'{FIELD_STEAM_USER}' not set");
+                None
+            }),
             _ => {
                 warn!("Unexpected key {key} provided");
                 None
@@ -101,6 +139,20 @@ impl Config {
         Ok(self.path_game.clone().map(|p| p.join(PATH_SEG_MODS)))
     }
 
+    pub fn get_target(&self, name: &str) -> Result<&TargetConf> {
+        self.targets.get(name).ok_or_else(|| {
+            anyhow!(
+                "Unknown target '{}', configured targets: {}",
+                name,
+                if self.targets.is_empty() {
+                    "none".to_string()
+                } else {
+                    self.targets.keys().cloned().collect::<Vec<_>>().join(", ")
+                }
+            )
+        })
+    }
+
     pub fn set(key: &str, value: &str) -> Result<()> {
         match key.to_lowercase().as_str() {
             FIELD_PATH_GAME => Self::write(|c| {
@@ -120,6 +172,17 @@ impl Config {
                 info!("Set '{}' to {}", FIELD_NO_ASK, c.no_ask);
                 Ok(())
             }),
+            FIELD_PATH_STEAMCMD => Self::write(|c| {
+                let value = PathBuf::from(value.trim());
+                info!("Set '{}' to {}", FIELD_PATH_STEAMCMD, value.display());
+                c.path_steamcmd = Some(value);
+                Ok(())
+            }),
+            FIELD_STEAM_USER => Self::write(|c| {
+                info!("Set '{}' to {}", FIELD_STEAM_USER, value.trim());
+                c.steam_user = Some(value.trim().to_string());
+                Ok(())
+            }),
             _ => {
                 error!("Unexpected key {key} provided");
                 Err(anyhow!("Unexpected key {key} provided"))
@@ -153,18 +216,26 @@ impl Config {
 
     fn default_make() -> Result<Self> {
         info!("Creating default config file");
-        let mut default = Self::default();
-        default.path_game = read_steam_install_path()
-            .unwrap_or_else(|_| {
-                warn!("Failed to read Steam install path, 'path_mods' will not be set");
-                None
-            })
-            .and_then(|path| {
-                path.join(PATH_SEG_RIMWORLD)
-                    .canonicalize()
-                    .inspect_err(|e| warn!("Failed to canonicalize '{}': {}", FIELD_PATH_GAME, e))
-                    .ok()
-            });
+        let default = Self {
+            path_game: read_steam_install_path()
+                .unwrap_or_else(|_| {
+                    warn!("Failed to read Steam install path, 'path_mods' will not be set");
+                    None
+                })
+                .and_then(|path| {
+                    find_steam_app_dir(&path, RIMWORLD_APP_ID)
+                        .unwrap_or_else(|| {
+                            debug!("RimWorld not found in Steam libraries, using default path");
+                            path.join(PATH_SEG_RIMWORLD)
+                        })
+                        .canonicalize()
+                        .inspect_err(|e| {
+                            warn!("Failed to canonicalize '{}': {}", FIELD_PATH_GAME, e)
+                        })
+                        .ok()
+                }),
+            ..Default::default()
+        };
         if let Some(path) = &default.path_game {
             debug!("Default '{}' set to {}", FIELD_PATH_GAME, path.display());
         }
@@ -236,6 +307,16 @@ impl ConfigArgs {
                     warn!("'path_mods' not configured");
                     any_err = true;
                 }
+                for (name, target) in &config.targets {
+                    if !target.path_game.exists() {
+                        warn!(
+                            "Target '{}': {} does not exist",
+                            name,
+                            target.path_game.display()
+                        );
+                        any_err = true;
+                    }
+                }
                 if !any_err {
                     info!("Config ready")
                 } else {
@@ -4,7 +4,16 @@ use anyhow::Result;
 use clap::{Args, Subcommand};
 use log::{debug, info, warn};
 
-use super::{PROJECT_CONFIG_FILE_NAME, PUBLISH_IGNORE_FILE_NAME, ProjectConf};
+use super::{
+    Config, DEFAULT_IGNORE_RULES, PROJECT_CONFIG_FILE_NAME, PUBLISH_IGNORE_FILE_NAME, ProjectConf,
+};
+use crate::{
+    about::{ABOUT_FILE_PATH, ModMetadata, make_package_id},
+    load_folders::{LOAD_FOLDERS_FILE_NAME, LoadFolders},
+    util::{confirm, prompt},
+};
+
+pub(super) const DEFAULT_GAME_VERSION: &str = "1.6";
 
 #[derive(Debug, Args)]
 pub struct GenerateArgs {
@@ -18,6 +27,10 @@ pub enum GenerateCommand {
     ConfigFile,
     /// Generate an ignore file for the mod.
     IgnoreFile,
+    /// Generate About/About.xml from the project configuration.
+    About,
+    /// Generate LoadFolders.xml from the version directories of the mod.
+    LoadFolders,
 }
 
 impl GenerateCommand {
@@ -32,6 +45,14 @@ impl GenerateCommand {
                 info!("Generating ignore file...");
                 gen_ignore_file(&working_dir)?;
             },
+            GenerateCommand::About => {
+                info!("Generating About.xml...");
+                gen_about_file(&working_dir)?;
+            },
+            GenerateCommand::LoadFolders => {
+                info!("Generating LoadFolders.xml...");
+                gen_load_folders_file(&working_dir)?;
+            },
         }
         Ok(())
     }
@@ -61,10 +82,130 @@ fn gen_ignore_file(working_dir: &Path) -> Result<()> {
         return Ok(());
     }
     debug!("Generating ignore file at {}", ignore_path.display());
-    fs::write(ignore_path, "# Add files or directories to ignore here\n")?;
+    let mut content = String::from(
+        "# Add files or directories to ignore here, using .gitignore syntax\n\
+         #\n\
+         # The following are ignored by default, re-include any of them with a\n\
+         # negated pattern such as '!*.psd'\n\
+         #\n",
+    );
+    for rule in DEFAULT_IGNORE_RULES {
+        content.push_str(&format!("#   {}\n", rule));
+    }
+    fs::write(ignore_path, content)?;
     Ok(())
 }
 
+/// The current user's name, as a default for the mod author.
+pub(super) fn default_author() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_default()
+}
+
+fn gen_about_file(working_dir: &Path) -> Result<()> {
+    let about_path = working_dir.join(ABOUT_FILE_PATH);
+    if about_path.exists() {
+        let meta = ModMetadata::load(working_dir)?;
+        warn!(
+            "About.xml already exists at {} for '{}' ({})",
+            about_path.display(),
+            meta.name,
+            meta.package_id
+        );
+        return Ok(());
+    }
+
+    let (mut config, _) = ProjectConf::load_current()?;
+    config.resolve_name();
+    let no_ask = Config::get_clone().no_ask;
+
+    // Configured values win, the rest is asked for unless prompts are disabled
+    let ask = |msg: &str, configured: Option<String>, default: String| {
+        configured.filter(|s| !s.is_empty()).unwrap_or_else(|| {
+            if no_ask {
+                default
+            } else {
+                prompt(msg, &default)
+            }
+        })
+    };
+
+    let author = ask("Author", config.author, default_author());
+    let package_id = ask(
+        "Package ID",
+        config.package_id,
+        make_package_id(&author, &config.name),
+    );
+    let supported_versions = ask(
+        "Supported versions (comma separated)",
+        Some(config.supported_versions.join(",")),
+        DEFAULT_GAME_VERSION.to_string(),
+    );
+    let description = ask("Description", config.description, String::new());
+
+    let meta = ModMetadata {
+        name: config.name,
+        authors: author
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(String::from)
+            .collect(),
+        package_id,
+        supported_versions: supported_versions
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(String::from)
+            .collect(),
+        description,
+        url: config.url,
+        ..Default::default()
+    };
+
+    debug!("Generating About.xml at {}", about_path.display());
+    meta.save(working_dir)
+}
+
+fn gen_load_folders_file(working_dir: &Path) -> Result<()> {
+    let (config, _) = ProjectConf::load_current()?;
+    let load_folders = LoadFolders::generate(
+        working_dir,
+        &config.load_folders,
+        &config.supported_versions,
+    )?;
+
+    let load_folders_path = working_dir.join(LOAD_FOLDERS_FILE_NAME);
+    if load_folders_path.exists()
+        && !Config::get_clone().no_ask
+        && !confirm(&format!(
+            "{} already exists. Do you want to overwrite it?",
+            load_folders_path.display()
+        ))
+    {
+        info!("Operation cancelled by user");
+        return Ok(());
+    }
+
+    for (version, folders) in &load_folders.versions {
+        debug!(
+            "v{}: {}",
+            version,
+            folders
+                .iter()
+                .map(|f| f.path.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+    debug!(
+        "Generating LoadFolders.xml at {}",
+        load_folders_path.display()
+    );
+    load_folders.save(working_dir)
+}
+
 impl GenerateArgs {
     pub fn run(&self) -> Result<()> {
         match self.command {
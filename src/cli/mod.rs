@@ -2,12 +2,21 @@ use anyhow::{Result, anyhow};
 use clap::{Parser, Subcommand};
 use log::warn;
 
-pub use self::{config::*, generate::*, project::*, publish::*};
+pub use self::{
+    changelog::*, check::*, config::*, generate::*, new::*, package::*, project::*, publish::*,
+    version::*, workshop::*,
+};
 
+mod changelog;
+mod check;
 mod config;
 mod generate;
+mod new;
+mod package;
 mod project;
 mod publish;
+mod version;
+mod workshop;
 
 #[derive(Debug, Parser)]
 #[command(name = "rimpub", version, about, long_about = None)]
@@ -26,12 +35,29 @@ pub enum Command {
     /// usually the RimWorld local mods folder.
     #[command(aliases = ["pub", "p"])]
     Publish(PublishArgs),
+    /// Package the mod into a zip archive for distribution outside Steam.
+    #[command(aliases = ["pkg"])]
+    Package(PackageArgs),
+    /// Manage the mod on the Steam Workshop.
+    #[command(aliases = ["ws"])]
+    Workshop(WorkshopArgs),
+    /// Manage the mod version.
+    #[command(aliases = ["ver", "v"])]
+    Version(VersionArgs),
+    /// Write the changes since the last version tag to CHANGELOG.md.
+    #[command(aliases = ["log"])]
+    Changelog(ChangelogArgs),
+    /// Check the mod files for problems without publishing.
+    Check(CheckArgs),
     /// Configure the mod publishing settings.
     #[command(aliases = ["cfg", "c"])]
     Config(ConfigArgs),
     /// Generate files for the mod.
     #[command(aliases = ["gen", "g"])]
     Generate(GenerateArgs),
+    /// Create a new mod project from a template.
+    #[command(aliases = ["init"])]
+    New(NewArgs),
 }
 
 impl Cli {
@@ -51,7 +77,13 @@ impl Cli {
             Some(ref command) => match command {
                 Command::Config(args) => args.run(),
                 Command::Publish(args) => args.run(),
+                Command::Package(args) => args.run(),
+                Command::Workshop(args) => args.run(),
+                Command::Version(args) => args.run(),
+                Command::Changelog(args) => args.run(),
+                Command::Check(args) => args.run(),
                 Command::Generate(args) => args.run(),
+                Command::New(args) => args.run(),
             },
             None => {
                 warn!("Choose an option, referring to '--help' for more info");
@@ -0,0 +1,565 @@
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::{Result, anyhow};
+use clap::Args;
+use ignore::WalkBuilder;
+use log::{debug, info};
+
+use super::{
+    PROJECT_CONFIG_FILE_NAME,
+    generate::{DEFAULT_GAME_VERSION, default_author},
+};
+use crate::{
+    about::{ModDependency, ModMetadata, make_package_id},
+    util::get_dir,
+    xml::{XmlWriter, escape},
+};
+
+const INITIAL_VERSION: &str = "0.1.0";
+const HARMONY_PACKAGE_ID: &str = "brrainz.harmony";
+const TEMPLATES_DIR_NAME: &str = "templates";
+/// Extensions of user template files whose placeholders are XML-escaped
+const XML_EXTENSIONS: &[&str] = &["xml", "csproj", "props", "targets"];
+/// Extension of user template files whose placeholders are escaped for C#
+/// string literals
+const CSHARP_EXTENSION: &str = "cs";
+
+/// A 1x1 transparent PNG, to be replaced by a real preview image
+const PREVIEW_PLACEHOLDER: &[u8] = &[
+    0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44, 0x52,
+    0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x08, 0x06, 0x00, 0x00, 0x00, 0x1f, 0x15, 0xc4,
+    0x89, 0x00, 0x00, 0x00, 0x0a, 0x49, 0x44, 0x41, 0x54, 0x78, 0x9c, 0x63, 0x00, 0x01, 0x00, 0x00,
+    0x05, 0x00, 0x01, 0x0d, 0x0a, 0x2d, 0xb4, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4e, 0x44, 0xae,
+    0x42, 0x60, 0x82,
+];
+
+const GITIGNORE: &str = "bin/\nobj/\n.vs/\n.idea/\n*.user\n/dist/\n";
+
+#[derive(Debug, Args)]
+pub struct NewArgs {
+    /// Name of the mod, also used for the project directory
+    pub name: String,
+
+    /// Project template: 'xml', 'csharp', 'harmony' or the name of a folder in
+    /// ~/.rimpub/templates
+    #[arg(short, long, default_value = "xml")]
+    pub template: String,
+
+    /// Mod author, defaults to the current user
+    #[arg(long)]
+    pub author: Option<String>,
+
+    /// Package id, defaults to 'Author.ModName'
+    #[arg(long)]
+    pub package_id: Option<String>,
+
+    /// Directory to create the project in, defaults to './<name>'
+    #[arg(long)]
+    pub dir: Option<PathBuf>,
+}
+
+/// Built-in project layouts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Template {
+    /// Defs, patches and textures only
+    Xml,
+    /// Adds a C# project building into `Assemblies`
+    CSharp,
+    /// Adds a C# project patching the game through Harmony
+    Harmony,
+}
+
+impl Template {
+    const NAMES: &[&str] = &["xml", "csharp", "harmony"];
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "xml" => Some(Template::Xml),
+            "csharp" | "cs" => Some(Template::CSharp),
+            "harmony" => Some(Template::Harmony),
+            _ => None,
+        }
+    }
+}
+
+/// Values a new project is filled in with.
+#[derive(Debug, Clone)]
+struct Scaffold {
+    name: String,
+    author: String,
+    package_id: String,
+    /// C# identifier derived from the name
+    namespace: String,
+}
+
+impl Scaffold {
+    /// Replaces `{{name}}`, `{{packageId}}`, `{{author}}` and `{{namespace}}`.
+    fn substitute(&self, text: &str) -> String {
+        self.substitute_with(text, str::to_string)
+    }
+
+    /// Like [`Scaffold::substitute`], escaping the values for XML content.
+    fn substitute_xml(&self, text: &str) -> String {
+        self.substitute_with(text, escape)
+    }
+
+    /// Like [`Scaffold::substitute`], escaping the values for C# string
+    /// literals.
+    fn substitute_csharp(&self, text: &str) -> String {
+        self.substitute_with(text, escape_csharp)
+    }
+
+    fn substitute_with(&self, text: &str, value: impl Fn(&str) -> String) -> String {
+        text.replace("{{name}}", &value(&self.name))
+            .replace("{{packageId}}", &value(&self.package_id))
+            .replace("{{author}}", &value(&self.author))
+            .replace("{{namespace}}", &value(&self.namespace))
+    }
+}
+
+impl NewArgs {
+    pub fn run(&self) -> Result<()> {
+        // Both are joined into paths, which must not leave their parent
+        check_path_name("template", &self.template)?;
+        check_path_name("mod name", &self.name)?;
+
+        // User templates may shadow the built-in ones
+        let templates_dir = get_dir().join(TEMPLATES_DIR_NAME);
+        let user_template = templates_dir.join(&self.template);
+        let template = match Template::from_name(&self.template) {
+            _ if user_template.is_dir() => None,
+            Some(template) => Some(template),
+            None => {
+                return Err(anyhow!(
+                    "Unknown template '{}', expected one of: {}",
+                    self.template,
+                    Template::NAMES
+                        .iter()
+                        .map(|name| name.to_string())
+                        .chain(list_user_templates(&templates_dir))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ));
+            },
+        };
+
+        let root = self
+            .dir
+            .clone()
+            .unwrap_or_else(|| std::env::current_dir().unwrap_or_default().join(&self.name));
+        if root.exists() && fs::read_dir(&root)?.next().is_some() {
+            return Err(anyhow!(
+                "'{}' already exists and is not empty",
+                root.display()
+            ));
+        }
+
+        let author = self.author.clone().unwrap_or_else(default_author);
+        if author.trim().is_empty() {
+            return Err(anyhow!("Cannot determine the author, pass '--author'"));
+        }
+        let scaffold = Scaffold {
+            package_id: self
+                .package_id
+                .clone()
+                .unwrap_or_else(|| make_package_id(&author, &self.name)),
+            namespace: make_namespace(&self.name),
+            name: self.name.clone(),
+            author,
+        };
+
+        match template {
+            Some(template) => {
+                info!("Creating {:?} project in {}", template, root.display());
+                write_template(&root, &scaffold, template)?;
+            },
+            None => {
+                info!(
+                    "Creating project from {} in {}",
+                    user_template.display(),
+                    root.display()
+                );
+                write_user_template(&user_template, &root, &scaffold)?;
+            },
+        }
+        info!(
+            "Created {} ({}), run 'rimpub publish' inside it to try it in game",
+            scaffold.name, scaffold.package_id
+        );
+        Ok(())
+    }
+}
+
+fn write_template(root: &Path, scaffold: &Scaffold, template: Template) -> Result<()> {
+    let harmony = template == Template::Harmony;
+    let meta = ModMetadata {
+        name: scaffold.name.clone(),
+        authors: vec![scaffold.author.clone()],
+        package_id: scaffold.package_id.clone(),
+        mod_version: Some(INITIAL_VERSION.to_string()),
+        supported_versions: vec![DEFAULT_GAME_VERSION.to_string()],
+        mod_dependencies: if harmony {
+            vec![ModDependency {
+                package_id: Some(HARMONY_PACKAGE_ID.to_string()),
+                display_name: Some("Harmony".to_string()),
+                steam_workshop_url: Some("steam://url/CommunityFilePage/2009463077".to_string()),
+                download_url: Some(
+                    "https://github.com/pardeike/HarmonyRimWorld/releases/latest".to_string(),
+                ),
+            }]
+        } else {
+            Vec::new()
+        },
+        load_after: if harmony {
+            vec![HARMONY_PACKAGE_ID.to_string()]
+        } else {
+            Vec::new()
+        },
+        ..Default::default()
+    };
+    meta.save(root)?;
+    write_file(root, "About/Preview.png", PREVIEW_PLACEHOLDER)?;
+
+    for dir in ["Defs", "Patches", "Textures"] {
+        fs::create_dir_all(root.join(dir))?;
+    }
+    let mut keyed = XmlWriter::new();
+    keyed.open("LanguageData").close("LanguageData");
+    write_file(
+        root,
+        &format!("Languages/English/Keyed/{}.xml", scaffold.namespace),
+        keyed.finish().as_bytes(),
+    )?;
+    write_file(root, ".gitignore", GITIGNORE.as_bytes())?;
+
+    let mut config = toml_edit::DocumentMut::new();
+    config["name"] = toml_edit::value(&scaffold.name);
+    config["version"] = toml_edit::value(INITIAL_VERSION);
+    config["author"] = toml_edit::value(&scaffold.author);
+    config["package_id"] = toml_edit::value(&scaffold.package_id);
+    config["supported_versions"] =
+        toml_edit::value(toml_edit::Array::from_iter([DEFAULT_GAME_VERSION]));
+
+    if template != Template::Xml {
+        let project = format!("Source/{0}/{0}.csproj", scaffold.namespace);
+        write_file(root, &project, csproj(scaffold, harmony).as_bytes())?;
+        write_file(
+            root,
+            &format!("Source/{0}/{0}Startup.cs", scaffold.namespace),
+            startup_class(scaffold, harmony).as_bytes(),
+        )?;
+        // There is no solution file for the build step to find
+        let mut build = toml_edit::Table::new();
+        build["project"] = toml_edit::value(project);
+        config["build"] = toml_edit::Item::Table(build);
+    }
+    write_file(
+        root,
+        PROJECT_CONFIG_FILE_NAME,
+        config.to_string().as_bytes(),
+    )?;
+
+    Ok(())
+}
+
+/// Copies a user template, filling in placeholders in text files as well as
+/// file and directory names.
+fn write_user_template(template_dir: &Path, root: &Path, scaffold: &Scaffold) -> Result<()> {
+    let walker = WalkBuilder::new(template_dir)
+        .standard_filters(false)
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .build();
+    for result in walker {
+        let entry = result?;
+        let relative = entry.path().strip_prefix(template_dir)?;
+        if relative.as_os_str().is_empty() {
+            continue;
+        }
+        let target = scaffold.substitute(&relative.to_string_lossy());
+        if Path::new(&target)
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_)))
+        {
+            return Err(anyhow!(
+                "Template path '{}' resolves to '{}', outside the project",
+                relative.display(),
+                target
+            ));
+        }
+        let target = root.join(target);
+
+        if entry.file_type().is_some_and(|ft| ft.is_dir()) {
+            fs::create_dir_all(&target)?;
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let content = fs::read(entry.path())?;
+        // Binary files such as textures are copied untouched
+        match String::from_utf8(content) {
+            Ok(text) if is_xml_file(&target) => fs::write(&target, scaffold.substitute_xml(&text))?,
+            Ok(text) if has_extension(&target, CSHARP_EXTENSION) => {
+                fs::write(&target, scaffold.substitute_csharp(&text))?
+            },
+            Ok(text) => fs::write(&target, scaffold.substitute(&text))?,
+            Err(e) => fs::write(&target, e.into_bytes())?,
+        }
+        debug!("Created {}", relative.display());
+    }
+    Ok(())
+}
+
+fn list_user_templates(templates_dir: &Path) -> Vec<String> {
+    let mut names = fs::read_dir(templates_dir)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_ok_and(|ft| ft.is_dir()))
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+/// Whether `path` holds XML, including MSBuild project files.
+fn is_xml_file(path: &Path) -> bool {
+    XML_EXTENSIONS.iter().any(|ext| has_extension(path, ext))
+}
+
+fn has_extension(path: &Path, extension: &str) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case(extension))
+}
+
+/// Rejects `value` if it cannot be used as a single path component.
+fn check_path_name(kind: &str, value: &str) -> Result<()> {
+    if value.trim().is_empty() || value == "." || value == ".." || value.contains(['/', '\\']) {
+        return Err(anyhow!(
+            "Invalid {} '{}', it must not be empty, '.', '..' or contain '/' or '\\'",
+            kind,
+            value
+        ));
+    }
+    Ok(())
+}
+
+/// Escapes `value` for use inside a C# string literal.
+fn escape_csharp(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn write_file(root: &Path, relative: &str, content: &[u8]) -> Result<()> {
+    let path = root.join(relative);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, content).map_err(|e| anyhow!("Failed to write {}: {}", relative, e))?;
+    debug!("Created {}", relative);
+    Ok(())
+}
+
+fn csproj(scaffold: &Scaffold, harmony: bool) -> String {
+    let harmony_reference = if harmony {
+        "\t\t<PackageReference Include=\"Lib.Harmony\" Version=\"2.*\" ExcludeAssets=\"runtime\" />\n"
+    } else {
+        ""
+    };
+    format!(
+        r#"<Project Sdk="Microsoft.NET.Sdk">
+
+	<PropertyGroup>
+		<TargetFramework>net472</TargetFramework>
+		<LangVersion>latest</LangVersion>
+		<RootNamespace>{namespace}</RootNamespace>
+		<AssemblyName>{namespace}</AssemblyName>
+		<Version>{version}</Version>
+		<OutputPath>../../Assemblies/</OutputPath>
+		<AppendTargetFrameworkToOutputPath>false</AppendTargetFrameworkToOutputPath>
+		<DebugType>none</DebugType>
+	</PropertyGroup>
+
+	<ItemGroup>
+		<PackageReference Include="Krafs.Rimworld.Ref" Version="{game}.*" />
+{harmony_reference}	</ItemGroup>
+
+</Project>
+"#,
+        namespace = scaffold.namespace,
+        version = INITIAL_VERSION,
+        game = DEFAULT_GAME_VERSION,
+    )
+}
+
+fn startup_class(scaffold: &Scaffold, harmony: bool) -> String {
+    let (using, patch) = if harmony {
+        (
+            "using HarmonyLib;\n",
+            format!(
+                "            new Harmony(\"{}\").PatchAll();\n",
+                escape_csharp(&scaffold.package_id)
+            ),
+        )
+    } else {
+        ("", String::new())
+    };
+    format!(
+        r#"{using}using Verse;
+
+namespace {namespace}
+{{
+    [StaticConstructorOnStartup]
+    public static class {namespace}Startup
+    {{
+        static {namespace}Startup()
+        {{
+{patch}            Log.Message("[{name}] Loaded");
+        }}
+    }}
+}}
+"#,
+        namespace = scaffold.namespace,
+        name = escape_csharp(&scaffold.name),
+    )
+}
+
+/// Turns a mod name into a valid C# identifier, e.g. `Cool Mod 2` into
+/// `CoolMod2`.
+fn make_namespace(name: &str) -> String {
+    let namespace = name
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '_')
+        .collect::<String>();
+    match namespace.chars().next() {
+        None => "Mod".to_string(),
+        Some(c) if c.is_ascii_digit() => format!("_{}", namespace),
+        Some(_) => namespace,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cli::ProjectConf;
+
+    #[test]
+    fn test_write_template() {
+        let root = tempfile::tempdir().unwrap();
+        let scaffold = Scaffold {
+            name: "Cool Mod".into(),
+            author: "Tom & Jerry".into(),
+            package_id: "Rhynia.CoolMod".into(),
+            namespace: make_namespace("Cool Mod"),
+        };
+        write_template(root.path(), &scaffold, Template::Harmony).unwrap();
+
+        let meta = ModMetadata::load(root.path()).unwrap();
+        assert!(meta.validate().is_empty(), "{:?}", meta.validate());
+        assert_eq!(meta.load_after, vec![HARMONY_PACKAGE_ID]);
+        for path in [
+            "About/Preview.png",
+            "Defs",
+            "Patches",
+            "Textures",
+            "Languages/English/Keyed/CoolMod.xml",
+            "Source/CoolMod/CoolMod.csproj",
+            "Source/CoolMod/CoolModStartup.cs",
+            ".gitignore",
+        ] {
+            assert!(root.path().join(path).exists(), "{} is missing", path);
+        }
+
+        let config = toml::from_str::<ProjectConf>(
+            &fs::read_to_string(root.path().join(PROJECT_CONFIG_FILE_NAME)).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(config.package_id.as_deref(), Some("Rhynia.CoolMod"));
+        assert_eq!(
+            config.build.project,
+            Some(PathBuf::from("Source/CoolMod/CoolMod.csproj"))
+        );
+    }
+
+    #[test]
+    fn test_write_user_template() {
+        let template = tempfile::tempdir().unwrap();
+        let root = tempfile::tempdir().unwrap();
+        let write = |path: &str, content: &[u8]| {
+            let path = template.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        };
+        write(
+            "About/About.xml",
+            b"<name>{{name}}</name><author>{{author}}</author><packageId>{{packageId}}</packageId>",
+        );
+        write(
+            "Source/{{namespace}}/Settings.cs",
+            b"namespace {{namespace}} { // by {{author}}\nconst string Name = \"{{name}}\";\n}",
+        );
+        write(".github/workflows/build.yml", b"name: {{name}}");
+        write("Textures/Icon.png", &[0x89, 0xff, 0xfe, 0x00]);
+        write(".git/HEAD", b"ref: refs/heads/main");
+
+        let scaffold = Scaffold {
+            name: "Cool \"Mod\"".into(),
+            author: "Tom & Jerry".into(),
+            package_id: "Rhynia.CoolMod".into(),
+            namespace: "CoolMod".into(),
+        };
+        write_user_template(template.path(), root.path(), &scaffold).unwrap();
+
+        let read = |path: &str| fs::read(root.path().join(path)).unwrap();
+        assert_eq!(
+            read("About/About.xml"),
+            b"<name>Cool &quot;Mod&quot;</name><author>Tom &amp; Jerry</author><packageId>Rhynia.CoolMod</packageId>"
+        );
+        assert_eq!(
+            read("Source/CoolMod/Settings.cs"),
+            b"namespace CoolMod { // by Tom & Jerry\nconst string Name = \"Cool \\\"Mod\\\"\";\n}"
+        );
+        assert_eq!(read(".github/workflows/build.yml"), b"name: Cool \"Mod\"");
+        assert_eq!(read("Textures/Icon.png"), [0x89, 0xff, 0xfe, 0x00]);
+        assert!(!root.path().join(".git").exists());
+
+        // Placeholders in paths must not leave the project
+        write("{{author}}/A.txt", b"");
+        let scaffold = Scaffold {
+            author: "..".into(),
+            ..scaffold
+        };
+        let other = tempfile::tempdir().unwrap();
+        assert!(write_user_template(template.path(), other.path(), &scaffold).is_err());
+    }
+
+    #[test]
+    fn test_check_path_name() {
+        assert!(check_path_name("template", "my-template").is_ok());
+        assert!(check_path_name("mod name", "Cool Mod..2").is_ok());
+        for value in ["", "..", "../../x", "a/b", "a\\b"] {
+            assert!(check_path_name("template", value).is_err(), "{}", value);
+        }
+        assert_eq!(escape_csharp("a \"b\" \\ c"), "a \\\"b\\\" \\\\ c");
+    }
+
+    #[test]
+    fn test_make_namespace() {
+        assert_eq!(make_namespace("Cool Mod"), "CoolMod");
+        assert_eq!(make_namespace("2nd Try!"), "_2ndTry");
+        assert_eq!(make_namespace("???"), "Mod");
+    }
+}
@@ -0,0 +1,247 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Result, anyhow};
+use clap::Args;
+use log::{debug, info, warn};
+use sha2::{Digest, Sha256};
+use zip::{CompressionMethod, DateTime, ZipWriter, write::SimpleFileOptions};
+
+use super::{
+    PROJECT_CONFIG_FILE_NAME, ProjectConf, SourceTree,
+    check::validate_source,
+    publish::{collect_source, run_build, validate_about},
+};
+use crate::util::format_size;
+
+const DEFAULT_OUTPUT_DIR: &str = "dist";
+
+#[derive(Debug, Args)]
+pub struct PackageArgs {
+    /// Directory to write the archive into, relative to the working directory
+    #[arg(short, long, default_value = DEFAULT_OUTPUT_DIR)]
+    pub output_dir: PathBuf,
+
+    /// Version used in the archive name, defaults to 'version' in
+    /// .rimpub.toml or <modVersion> in About/About.xml
+    #[arg(long, value_name = "VERSION")]
+    pub mod_version: Option<String>,
+
+    /// Also write a SHA-256 checksum file next to the archive
+    #[arg(long)]
+    pub checksum: bool,
+
+    /// Only package the folders the given game version loads, e.g. '1.5'
+    #[arg(long, value_name = "VERSION")]
+    pub game_version: Option<String>,
+
+    /// Skip the build step, e.g. for changes without code
+    #[arg(long)]
+    pub no_build: bool,
+
+    /// Skip validating About/About.xml and XML files before packaging
+    #[arg(long)]
+    pub no_validate: bool,
+}
+
+impl PackageArgs {
+    pub fn run(&self) -> Result<()> {
+        let working_directory = std::env::current_dir()?;
+        info!("Working directory: {}", working_directory.display());
+
+        let (mut config, _) = ProjectConf::load_current()?;
+        config.resolve_name();
+        info!("Working project: {}", config.name);
+
+        let build_report = if self.no_build {
+            info!("Skipping build step");
+            None
+        } else {
+            Some(run_build(&working_directory, &config.build)?)
+        };
+
+        if self.no_validate {
+            debug!("Skipping About.xml validation");
+        } else {
+            validate_about(&working_directory)?;
+        }
+
+        let output_dir = working_directory.join(&self.output_dir);
+        let (mut source, any_err) =
+            collect_source(&working_directory, &config, self.game_version.as_deref())?;
+        // Keep earlier archives out of the new one
+        if let Ok(relative) = output_dir.strip_prefix(&working_directory)
+            && !relative.as_os_str().is_empty()
+        {
+            source.retain(|path, _| !path.starts_with(relative));
+        }
+        if !self.no_validate {
+            validate_source(&source)?;
+        }
+
+        let version = self
+            .mod_version
+            .clone()
+            .or_else(|| config.resolve_version(&working_directory));
+        if version.is_none() {
+            warn!(
+                "No version in {} or About/About.xml, pass '--mod-version' to name the archive",
+                PROJECT_CONFIG_FILE_NAME
+            );
+        }
+        let root_name = file_name_safe(&config.name);
+        let stem = archive_stem(&root_name, version.as_deref(), self.game_version.as_deref());
+
+        fs::create_dir_all(&output_dir)
+            .map_err(|e| anyhow!("Failed to create output directory: {}", e))?;
+        let archive = output_dir.join(format!("{}.zip", stem));
+        write_archive(&source, &root_name, &archive)?;
+        info!(
+            "Packaged {} file(s) into {} ({})",
+            source.files.len(),
+            archive.display(),
+            format_size(fs::metadata(&archive)?.len())
+        );
+
+        if self.checksum {
+            let checksum_path = output_dir.join(format!("{}.zip.sha256", stem));
+            let hash = sha256_file(&archive)?;
+            fs::write(&checksum_path, format!("{}  {}.zip\n", hash, stem))?;
+            info!("Checksum written to {}", checksum_path.display());
+        }
+
+        if let Some(report) = build_report {
+            report.print_summary();
+        }
+
+        if any_err {
+            warn!("Error encountered while walking the source directory.")
+        }
+        Ok(())
+    }
+}
+
+/// Writes `source` under a `root_name` folder, in sorted order with fixed
+/// timestamps and permissions so identical inputs give identical archives.
+fn write_archive(source: &SourceTree, root_name: &str, archive: &Path) -> Result<()> {
+    let file = File::create(archive)
+        .map_err(|e| anyhow!("Failed to create {}: {}", archive.display(), e))?;
+    let mut zip = ZipWriter::new(BufWriter::new(file));
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .last_modified_time(DateTime::default());
+    let dir_options = options.unix_permissions(0o755);
+    let file_options = options.unix_permissions(0o644);
+
+    zip.add_directory(format!("{}/", root_name), dir_options)?;
+    for relative in &source.dirs {
+        zip.add_directory(
+            format!("{}/{}/", root_name, entry_name(relative)),
+            dir_options,
+        )?;
+    }
+    for relative in &source.files {
+        zip.start_file(
+            format!("{}/{}", root_name, entry_name(relative)),
+            file_options,
+        )?;
+        let mut input = File::open(source.root.join(relative))
+            .map_err(|e| anyhow!("Failed to read {}: {}", relative.display(), e))?;
+        io::copy(&mut input, &mut zip)?;
+        debug!("Packaged file: {}", relative.display());
+    }
+
+    zip.finish()?;
+    Ok(())
+}
+
+/// Zip entries always use `/`, whatever the platform separator is.
+fn entry_name(relative: &Path) -> String {
+    relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Archive name without extension, like `My-Mod-1.2.0-1.5` when packaged for
+/// a single game version.
+fn archive_stem(name: &str, version: Option<&str>, game_version: Option<&str>) -> String {
+    [Some(name), version, game_version]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// `name` without characters that are invalid in file names on any platform,
+/// and without the leading and trailing dots of `.` and `..`.
+pub(super) fn file_name_safe(name: &str) -> String {
+    name.chars()
+        .filter(|c| !matches!(c, '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*'))
+        .map(|c| if c.is_whitespace() { '-' } else { c })
+        .collect::<String>()
+        .trim_matches('.')
+        .to_string()
+}
+
+fn sha256_file(path: &Path) -> Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, SystemTime};
+
+    use zip::ZipArchive;
+
+    use super::*;
+
+    #[test]
+    fn test_write_archive() {
+        let root = tempfile::tempdir().unwrap();
+        for path in ["About/About.xml", "1.5/Defs/Things.xml", "Textures/A.png"] {
+            let path = root.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "content").unwrap();
+        }
+        let (source, _) = SourceTree::collect(root.path()).unwrap();
+
+        let out = tempfile::tempdir().unwrap();
+        let first = out.path().join("first.zip");
+        let second = out.path().join("second.zip");
+        write_archive(&source, "My Mod", &first).unwrap();
+        // Touching a file must not change the archive
+        File::options()
+            .write(true)
+            .open(root.path().join("Textures/A.png"))
+            .unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(3600))
+            .unwrap();
+        write_archive(&source, "My Mod", &second).unwrap();
+        assert_eq!(fs::read(&first).unwrap(), fs::read(&second).unwrap());
+
+        let archive = ZipArchive::new(File::open(&first).unwrap()).unwrap();
+        let names = archive.file_names().collect::<Vec<_>>();
+        assert!(names.contains(&"My Mod/1.5/Defs/Things.xml"));
+        assert!(names.iter().all(|name| name.starts_with("My Mod/")));
+    }
+
+    #[test]
+    fn test_file_name_safe() {
+        assert_eq!(file_name_safe("Cool Mod: Reloaded?"), "Cool-Mod-Reloaded");
+        assert_eq!(file_name_safe("../Mods/A"), "ModsA");
+        assert_eq!(archive_stem("Mod", Some("1.0"), None), "Mod-1.0");
+        assert_eq!(archive_stem("Mod", Some("1.0"), Some("1.5")), "Mod-1.0-1.5");
+        assert_eq!(archive_stem("Mod", None, Some("1.5")), "Mod-1.5");
+    }
+}
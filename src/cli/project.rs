@@ -1,15 +1,163 @@
-use std::fs;
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
 
 use anyhow::{Result, anyhow};
 use log::{debug, warn};
 use serde::{Deserialize, Serialize};
 
+use crate::about::ModMetadata;
+
 pub const PROJECT_CONFIG_FILE_NAME: &str = ".rimpub.toml";
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ProjectConf {
     #[serde(default)]
     pub name: String,
+
+    #[serde(default)]
+    pub version: Option<String>,
+
+    #[serde(default)]
+    pub author: Option<String>,
+
+    #[serde(default)]
+    pub package_id: Option<String>,
+
+    #[serde(default)]
+    pub supported_versions: Vec<String>,
+
+    #[serde(default)]
+    pub description: Option<String>,
+
+    #[serde(default)]
+    pub url: Option<String>,
+
+    #[serde(default)]
+    pub load_folders: LoadFoldersConf,
+
+    #[serde(default)]
+    pub build: BuildConf,
+
+    #[serde(default)]
+    pub assemblies: AssembliesConf,
+
+    #[serde(default)]
+    pub check: CheckConf,
+}
+
+/// Settings for generating `LoadFolders.xml`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoadFoldersConf {
+    /// Load the mod root folder for every version
+    pub include_root: bool,
+    /// Folders loaded for every version, defaults to `Common` if it exists
+    pub shared: Vec<String>,
+    /// Folders only loaded when other mods are (not) active
+    pub conditional: Vec<ConditionalFolder>,
+}
+
+impl Default for LoadFoldersConf {
+    fn default() -> Self {
+        LoadFoldersConf {
+            include_root: true,
+            shared: Vec::new(),
+            conditional: Vec::new(),
+        }
+    }
+}
+
+/// Settings for the build step run before publishing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BuildConf {
+    /// Solution or project file to build, defaults to every `.sln` found
+    pub project: Option<PathBuf>,
+    /// How many directory levels to search for `.sln` files
+    pub search_depth: usize,
+    /// Configuration passed to `dotnet build`
+    pub configuration: String,
+    /// Extra arguments passed to `dotnet build`
+    pub args: Vec<String>,
+    /// Environment variables set for the build
+    pub env: BTreeMap<String, String>,
+    /// Program and arguments replacing `dotnet build` entirely
+    pub command: Vec<String>,
+    /// What to do when assemblies whose `<OutputPath>` leads into an
+    /// `Assemblies` folder are missing or stale there
+    pub check_assemblies: AssemblyCheck,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum AssemblyCheck {
+    Off,
+    #[default]
+    Warn,
+    Error,
+}
+
+impl Default for BuildConf {
+    fn default() -> Self {
+        BuildConf {
+            project: None,
+            search_depth: 4,
+            configuration: "Release".to_string(),
+            args: Vec::new(),
+            env: BTreeMap::new(),
+            command: Vec::new(),
+            check_assemblies: AssemblyCheck::default(),
+        }
+    }
+}
+
+/// Settings for reference-only assemblies found in `Assemblies` folders.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct AssembliesConf {
+    /// What to do with matched assemblies
+    pub references: ReferenceAction,
+    /// File name patterns published even if matched, e.g. `0Harmony.dll`
+    pub allow: Vec<String>,
+    /// File name patterns treated as references in addition to the defaults
+    pub deny: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ReferenceAction {
+    Off,
+    Warn,
+    #[default]
+    Exclude,
+}
+
+/// Settings for checking the mod files.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct CheckConf {
+    /// Mods whose defs may be referenced, as folder paths or package ids
+    /// found in the game's `Data` and `Mods` folders or the Workshop, in
+    /// addition to `modDependencies` in About.xml
+    pub dependencies: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ConditionalFolder {
+    /// Folder path, where `{version}` is replaced by the game version
+    pub folder: String,
+    /// Comma separated package ids that must be active
+    #[serde(default)]
+    pub if_mod_active: Option<String>,
+    /// Comma separated package ids that must not be active
+    #[serde(default)]
+    pub if_mod_not_active: Option<String>,
+    /// Game versions this folder applies to, all versions if empty
+    #[serde(default)]
+    pub versions: Vec<String>,
 }
 
 impl ProjectConf {
@@ -27,6 +175,30 @@ impl ProjectConf {
         }
     }
 
+    /// The configured version, falling back to `<modVersion>` in About.xml.
+    pub(super) fn resolve_version(&self, working_directory: &Path) -> Option<String> {
+        self.version.clone().or_else(|| {
+            ModMetadata::load(working_directory)
+                .ok()
+                .and_then(|meta| meta.mod_version)
+        })
+    }
+
+    /// The project config with `version` set, keeping its formatting.
+    pub(super) fn with_version(working_directory: &Path, version: &str) -> Result<String> {
+        let config_path = working_directory.join(PROJECT_CONFIG_FILE_NAME);
+        let content = match fs::read_to_string(&config_path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+        let mut doc = content
+            .parse::<toml_edit::DocumentMut>()
+            .map_err(|e| anyhow!("Failed to parse {}: {}", PROJECT_CONFIG_FILE_NAME, e))?;
+        doc["version"] = toml_edit::value(version);
+        Ok(doc.to_string())
+    }
+
     pub(super) fn load_current() -> Result<(Self, bool)> {
         let working_dir = std::env::current_dir()?;
         let config_path = working_dir.join(PROJECT_CONFIG_FILE_NAME);
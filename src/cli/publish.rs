@@ -1,112 +1,430 @@
 use std::{
-    fs,
+    fs::{self, File},
     path::{Path, PathBuf},
-    process::Command,
 };
 
 use anyhow::{Result, anyhow};
 use clap::Args;
-use ignore::{DirEntry, WalkBuilder};
-use log::{debug, info, warn};
+use ignore::{
+    WalkBuilder,
+    gitignore::{Gitignore, GitignoreBuilder},
+};
+use log::{debug, error, info, warn};
 
+pub(super) use self::build::{find_files, run_build};
+use self::{
+    assemblies::ReferenceFilter,
+    link::link_dir,
+    sync::{CompareMode, SyncPlan},
+};
+use super::check::validate_source;
 use crate::{
+    about::{ABOUT_FILE_PATH, ModMetadata},
     cli::{Config, PROJECT_CONFIG_FILE_NAME, ProjectConf},
-    util::{confirm, decode_out},
+    load_folders::VersionFilter,
+    util::{confirm, format_size},
 };
 
+mod assemblies;
+mod build;
+mod diagnostics;
+mod link;
+mod sync;
+mod verify;
+
 #[derive(Debug, Args)]
 pub struct PublishArgs {
     /// Alternate target_dir used to copy files
-    #[arg(long)]
+    #[arg(long, conflicts_with_all = ["target", "all_targets"])]
     pub target_dir: Option<String>,
+
+    /// Publish to the named targets from the global config, e.g.
+    /// 'stable,unstable'
+    #[arg(short, long, value_delimiter = ',', value_name = "NAMES")]
+    pub target: Vec<String>,
+
+    /// Publish to every target configured in the global config
+    #[arg(long, conflicts_with = "target")]
+    pub all_targets: bool,
+
+    /// Only copy changed files and remove stale ones, instead of recreating
+    /// the target directory
+    #[arg(short, long)]
+    pub sync: bool,
+
+    /// Compare file contents instead of size and modification time when
+    /// syncing
+    #[arg(long, requires = "sync")]
+    pub checksum: bool,
+
+    /// Print the files that would be copied and deleted without touching the
+    /// target directory
+    #[arg(short = 'n', long)]
+    pub dry_run: bool,
+
+    /// Only publish the folders the given game version loads, e.g. '1.5'
+    #[arg(long, value_name = "VERSION")]
+    pub game_version: Option<String>,
+
+    /// Skip the build step, e.g. for changes without code
+    #[arg(long)]
+    pub no_build: bool,
+
+    /// Skip validating About/About.xml and XML files before publishing
+    #[arg(long)]
+    pub no_validate: bool,
+
+    /// Link the target directory to the working directory instead of copying,
+    /// so edits show up on the next game load. Nothing is built or copied
+    #[arg(long, conflicts_with_all = ["sync", "game_version"])]
+    pub link: bool,
 }
 
 pub const PUBLISH_IGNORE_FILE_NAME: &str = ".rimpub.ignore";
 
+/// Development files never needed by RimWorld, excluded unless re-included
+/// with a negated pattern in [`PUBLISH_IGNORE_FILE_NAME`].
+pub const DEFAULT_IGNORE_RULES: &[&str] = &[
+    "Source/",
+    "*.csproj",
+    "*.sln",
+    "obj/",
+    "bin/",
+    ".vs/",
+    ".idea/",
+    "*.psd",
+    "*.xcf",
+    "*.blend",
+    "*.pdb",
+    ".rimpub.*",
+    "/dist/",
+];
+
 impl PublishArgs {
     pub fn run(&self) -> Result<()> {
         let config_global = Config::get_clone();
         let working_directory = std::env::current_dir()?;
         info!("Working directory: {}", working_directory.display());
 
-        if let Some(sln) = find_sln_file(&working_directory)? {
-            info!("Found solution file, executing build: {}", sln.display());
-            execute_dotnet_build(&sln)?;
-        }
-
         let (mut config, _) = ProjectConf::load_current()?;
         config.resolve_name();
         debug!("Loaded project configuration: {:?}", config);
 
         info!("Working project: {}", config.name);
+        let version = config.resolve_version(&working_directory);
+        if let Some(version) = &version {
+            info!("Version: {}", version);
+        }
 
-        let target_base = self
+        let build_report = if self.dry_run {
+            info!("Dry run, skipping build step");
+            None
+        } else if self.link {
+            info!("Linking, skipping build step");
+            None
+        } else if self.no_build {
+            info!("Skipping build step");
+            None
+        } else {
+            Some(run_build(&working_directory, &config.build)?)
+        };
+
+        if self.no_validate {
+            debug!("Skipping About.xml validation");
+        } else {
+            validate_about(&working_directory)?;
+        }
+
+        let target_bases = self.target_bases(&config_global)?;
+
+        // The project itself is linked, so there is no file tree to collect
+        if self.link {
+            for (_, target_base) in &target_bases {
+                link_dir(
+                    &working_directory,
+                    &target_base.join(&config.name),
+                    config_global.no_ask,
+                    self.dry_run,
+                )?;
+            }
+            return Ok(());
+        }
+
+        let (source, mut any_err) =
+            collect_source(&working_directory, &config, self.game_version.as_deref())?;
+        if !self.no_validate {
+            validate_source(&source)?;
+        }
+
+        for (name, target_base) in &target_bases {
+            let target_path = target_base.join(&config.name);
+            match name {
+                Some(name) => info!("Target '{}': {}", name, target_path.display()),
+                None => info!("Target directory: {}", target_path.display()),
+            }
+            any_err |= self.publish_to(&source, &target_path, &config_global)?;
+        }
+
+        if self.dry_run {
+            if any_err {
+                warn!("Error encountered while walking the source directory.")
+            }
+            return Ok(());
+        }
+
+        if let Some(report) = build_report {
+            report.print_summary();
+        }
+
+        if any_err {
+            warn!("Error encountered during processing.")
+        } else {
+            match &version {
+                Some(version) => info!("Successfully processed {} {}", config.name, version),
+                None => info!("Successfully processed {}", config.name),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Mods directories to publish into, with the target name if it came from
+    /// the global config.
+    fn target_bases(&self, config: &Config) -> Result<Vec<(Option<String>, PathBuf)>> {
+        let targets = if self.all_targets {
+            if config.targets.is_empty() {
+                return Err(anyhow!("No targets configured in the global config"));
+            }
+            config
+                .targets
+                .iter()
+                .map(|(name, target)| (name, target.get_path_mods()))
+                .collect::<Vec<_>>()
+        } else {
+            self.target
+                .iter()
+                .map(|name| Ok((name, config.get_target(name)?.get_path_mods())))
+                .collect::<Result<Vec<_>>>()?
+        };
+        if !targets.is_empty() {
+            // Targets sharing a game install are published once
+            let mut bases = Vec::new();
+            for (name, base) in targets {
+                if !bases.iter().any(|(_, b)| *b == base) {
+                    bases.push((Some(name.clone()), base));
+                }
+            }
+            return Ok(bases);
+        }
+
+        let base = self
             .target_dir
             .as_ref()
             .map(PathBuf::from)
-            .or_else(|| config_global.get_path_mods().ok().flatten())
+            .or_else(|| config.get_path_mods().ok().flatten())
             .ok_or_else(|| anyhow!("Cannot determine target directory from config or args"))?;
-        let target_path = target_base.join(&config.name);
+        Ok(vec![(None, base)])
+    }
+
+    /// Copies `source` into `target_path`, returning whether any file failed.
+    fn publish_to(&self, source: &SourceTree, target_path: &Path, config: &Config) -> Result<bool> {
+        let is_link = fs::symlink_metadata(target_path).is_ok_and(|m| m.file_type().is_symlink());
+        // Syncing through a link would delete ignored files from the project
+        if self.sync && is_link {
+            return Err(anyhow!(
+                "'{}' is linked by '--link', remove the link before syncing",
+                target_path.display()
+            ));
+        }
 
-        info!("Target directory: {}", target_path.display());
+        if self.dry_run {
+            let plan = if self.sync {
+                SyncPlan::build(source, target_path, self.compare_mode())?
+            } else if is_link {
+                // Only the link is removed, never the project it points at
+                info!("Would replace link: {}", target_path.display());
+                SyncPlan::copy(source)
+            } else {
+                SyncPlan::full(source, target_path)?
+            };
+            print_plan(&plan, &source.root);
+            return Ok(false);
+        }
 
-        if target_path.exists() {
-            if !config_global.no_ask {
-                if !confirm(&format!(
+        let mut any_err = false;
+        if target_path.exists() && !self.sync {
+            if !config.no_ask
+                && !confirm(&format!(
                     "Target directory '{}' already exists. Do you want to delete it and continue? (y/N): ",
                     target_path.display()
-                )) {
-                    info!("Operation cancelled by user");
-                    return Ok(());
-                }
+                ))
+            {
+                info!("Skipped {} as cancelled by user", target_path.display());
+                return Ok(false);
             }
 
             info!(
                 "Clearing existing target directory: {}",
                 target_path.display()
             );
-            fs::remove_dir_all(&target_path).map_err(|err| {
+            fs::remove_dir_all(target_path).map_err(|err| {
                 let msg = format!("Failed to remove existing target directory: {}", err);
                 warn!("{}", msg);
                 anyhow!("{}", msg)
             })?;
         }
 
-        fs::create_dir_all(&target_path).map_err(|e| {
+        fs::create_dir_all(target_path).map_err(|e| {
             let msg = format!("Failed to create target directory: {}", e);
             warn!("{}", msg);
             anyhow!("{}", msg)
         })?;
 
-        let mut builder = WalkBuilder::new(&working_directory);
-        builder
-            .git_ignore(true)
-            .git_exclude(true)
-            .git_global(true)
-            .add_custom_ignore_filename(PUBLISH_IGNORE_FILE_NAME)
-            .filter_entry(|entry| {
-                let path = entry.path();
-                let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
-
-                if name == ".gitignore"
-                    || name == ".git"
-                    || name == PUBLISH_IGNORE_FILE_NAME
-                    || name == PROJECT_CONFIG_FILE_NAME
-                {
-                    false
-                } else {
-                    true
+        if self.sync {
+            let plan = SyncPlan::build(source, target_path, self.compare_mode())?;
+            let stats = plan.apply(&source.root, target_path);
+            info!(
+                "Synced files: {} added, {} updated, {} removed, {} unchanged",
+                stats.added, stats.updated, stats.removed, stats.unchanged
+            );
+            any_err |= stats.failed > 0;
+        } else {
+            for relative in &source.dirs {
+                if let Err(e) = fs::create_dir_all(target_path.join(relative)) {
+                    warn!("Failed to create {}: {}", relative.display(), e);
+                    any_err = true;
+                }
+            }
+            for relative in &source.files {
+                match copy_file(&source.root.join(relative), &target_path.join(relative)) {
+                    Ok(()) => debug!("Copied file: {}", relative.display()),
+                    Err(e) => {
+                        warn!("Failed to copy {}: {}", relative.display(), e);
+                        any_err = true;
+                    },
                 }
-            });
+            }
+        }
+        Ok(any_err)
+    }
+
+    fn compare_mode(&self) -> CompareMode {
+        if self.checksum {
+            CompareMode::Content
+        } else {
+            CompareMode::Metadata
+        }
+    }
+}
+
+/// Makes `target_path` an exact copy of `source`, only touching changed files,
+/// returning whether any file failed.
+pub(super) fn mirror(source: &SourceTree, target_path: &Path) -> Result<bool> {
+    fs::create_dir_all(target_path)?;
+    let stats = SyncPlan::build(source, target_path, CompareMode::Metadata)?
+        .apply(&source.root, target_path);
+    debug!(
+        "Mirrored files: {} added, {} updated, {} removed, {} unchanged",
+        stats.added, stats.updated, stats.removed, stats.unchanged
+    );
+    Ok(stats.failed > 0)
+}
+
+/// Collects the files to publish, narrowed to `game_version` if given and
+/// without reference-only assemblies.
+pub(super) fn collect_source(
+    working_directory: &Path,
+    config: &ProjectConf,
+    game_version: Option<&str>,
+) -> Result<(SourceTree, bool)> {
+    let (mut source, any_err) = SourceTree::collect(working_directory)?;
+    if let Some(version) = game_version {
+        info!("Publishing for game version {}", version);
+        let filter = VersionFilter::new(working_directory, version)?;
+        source.retain(|path, is_dir| filter.includes(path, is_dir));
+    }
+    ReferenceFilter::new(&config.assemblies).apply(&mut source);
+    Ok((source, any_err))
+}
+
+pub(super) fn validate_about(working_directory: &Path) -> Result<()> {
+    let problems = match ModMetadata::load(working_directory) {
+        Ok(meta) => meta.validate(),
+        Err(e) => vec![e.to_string()],
+    };
+    if problems.is_empty() {
+        debug!("{} is valid", ABOUT_FILE_PATH);
+        return Ok(());
+    }
 
-        let walker = builder.build();
+    for problem in &problems {
+        error!("{}: {}", ABOUT_FILE_PATH, problem);
+    }
+    Err(anyhow!(
+        "{} has {} problem(s), fix them or pass '--no-validate'",
+        ABOUT_FILE_PATH,
+        problems.len()
+    ))
+}
+
+fn print_plan(plan: &SyncPlan, source_root: &Path) {
+    for relative in &plan.added {
+        info!("Would copy: {}", relative.display());
+    }
+    for relative in &plan.updated {
+        info!("Would update: {}", relative.display());
+    }
+    for relative in &plan.removed {
+        info!("Would delete: {}", relative.display());
+    }
+
+    let total_size = plan
+        .added
+        .iter()
+        .chain(&plan.updated)
+        .filter_map(|relative| fs::metadata(source_root.join(relative)).ok())
+        .map(|meta| meta.len())
+        .sum::<u64>();
+    info!(
+        "Dry run: {} to copy ({}), {} to delete, {} unchanged",
+        plan.added.len() + plan.updated.len(),
+        format_size(total_size),
+        plan.removed.len(),
+        plan.unchanged
+    );
+}
+
+/// Files and directories of a mod project that should be published, as paths
+/// relative to `root`.
+#[derive(Debug, Default)]
+pub struct SourceTree {
+    pub root: PathBuf,
+    pub files: Vec<PathBuf>,
+    pub dirs: Vec<PathBuf>,
+}
+
+impl SourceTree {
+    /// Walks `root` honoring git ignore rules and [`PUBLISH_IGNORE_FILE_NAME`],
+    /// also reporting whether any entry failed to be read.
+    pub fn collect(root: &Path) -> Result<(Self, bool)> {
+        let mut tree = SourceTree {
+            root: root.to_path_buf(),
+            ..Default::default()
+        };
         let mut any_err = false;
-        for result in walker {
+
+        for result in build_walker(root).build() {
             match result {
                 Ok(entry) => {
-                    if let Err(e) = copy_entry(&entry, &working_directory, &target_path) {
-                        warn!("Failed to copy {}: {}", entry.path().display(), e);
-                        any_err = true;
+                    let path = entry.path();
+                    if path == root {
+                        continue;
+                    }
+                    let relative = path.strip_prefix(root)?.to_path_buf();
+                    match entry.file_type() {
+                        Some(ft) if ft.is_dir() => tree.dirs.push(relative),
+                        Some(ft) if ft.is_file() => tree.files.push(relative),
+                        _ => debug!("Skipping special file: {}", relative.display()),
                     }
                 },
                 Err(e) => {
@@ -116,67 +434,237 @@ impl PublishArgs {
             }
         }
 
-        if any_err {
-            warn!("Error encountered during processing.")
-        } else {
-            info!("Successfully processed {}", config.name)
+        tree.dirs.sort();
+        tree.files.sort();
+        Ok((tree, any_err))
+    }
+
+    /// Keeps only entries for which `keep(relative_path, is_dir)` holds.
+    pub fn retain(&mut self, mut keep: impl FnMut(&Path, bool) -> bool) {
+        self.dirs.retain(|path| keep(path, true));
+        self.files.retain(|path| keep(path, false));
+    }
+}
+
+fn build_walker(root: &Path) -> WalkBuilder {
+    let defaults = default_ignore(root);
+    // Negations in the root ignore file re-include default exclusions
+    let overrides = {
+        let mut builder = GitignoreBuilder::new(root);
+        if let Some(e) = builder.add(root.join(PUBLISH_IGNORE_FILE_NAME)) {
+            debug!("Not reading {}: {}", PUBLISH_IGNORE_FILE_NAME, e);
         }
+        builder.build().unwrap_or_else(|e| {
+            warn!("Failed to read {}: {}", PUBLISH_IGNORE_FILE_NAME, e);
+            Gitignore::empty()
+        })
+    };
 
-        Ok(())
+    let mut builder = WalkBuilder::new(root);
+    builder
+        .git_ignore(true)
+        .git_exclude(true)
+        .git_global(true)
+        .add_custom_ignore_filename(PUBLISH_IGNORE_FILE_NAME)
+        .filter_entry(move |entry| {
+            let name = entry.file_name().to_str().unwrap_or("");
+            if name == ".gitignore"
+                || name == ".git"
+                || name == PUBLISH_IGNORE_FILE_NAME
+                || name == PROJECT_CONFIG_FILE_NAME
+            {
+                return false;
+            }
+
+            let path = entry.path();
+            let is_dir = entry.file_type().is_some_and(|ft| ft.is_dir());
+            !defaults
+                .matched_path_or_any_parents(path, is_dir)
+                .is_ignore()
+                || overrides
+                    .matched_path_or_any_parents(path, is_dir)
+                    .is_whitelist()
+        });
+    builder
+}
+
+fn default_ignore(root: &Path) -> Gitignore {
+    let mut builder = GitignoreBuilder::new(root);
+    for rule in DEFAULT_IGNORE_RULES {
+        builder
+            .add_line(None, rule)
+            .expect("Default ignore rules should be valid");
     }
+    builder
+        .build()
+        .expect("Default ignore rules should be valid")
 }
 
-fn copy_entry(entry: &DirEntry, source_root: &Path, target_root: &Path) -> Result<()> {
-    let source_path = entry.path();
+/// Copies a single file, creating parent directories and carrying over the
+/// modification time so later syncs can detect unchanged files.
+pub(crate) fn copy_file(source: &Path, target: &Path) -> Result<()> {
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if target.is_dir() {
+        fs::remove_dir_all(target)?;
+    } else if target.is_file() {
+        make_writable(target)?;
+    }
+    fs::copy(source, target)?;
+
+    // The copy takes over the source permissions, so a read-only file is
+    // made writable until its modification time is set
+    let meta = fs::metadata(source)?;
+    make_writable(target)?;
+    File::options()
+        .write(true)
+        .open(target)?
+        .set_modified(meta.modified()?)?;
+    fs::set_permissions(target, meta.permissions())?;
+    Ok(())
+}
 
-    if source_path == source_root {
+/// Grants the owner write access to `path` if it is read-only.
+fn make_writable(path: &Path) -> Result<()> {
+    let mut permissions = fs::metadata(path)?.permissions();
+    if !permissions.readonly() {
         return Ok(());
     }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        permissions.set_mode(permissions.mode() | 0o200);
+    }
+    #[cfg(not(unix))]
+    #[allow(clippy::permissions_set_readonly_false)]
+    permissions.set_readonly(false);
+    fs::set_permissions(path, permissions)?;
+    Ok(())
+}
 
-    let relative_path = source_path.strip_prefix(source_root)?;
-    let target_path = target_root.join(relative_path);
+#[cfg(test)]
+mod test {
+    use super::*;
 
-    if entry.file_type().map_or(false, |ft| ft.is_dir()) {
-        if !target_path.exists() {
-            fs::create_dir_all(&target_path)?;
-            debug!("Created directory: {}", relative_path.display());
+    #[test]
+    fn test_default_ignore_rules() {
+        let root = tempfile::tempdir().unwrap();
+        for path in [
+            "About/About.xml",
+            "Source/MyMod/Main.cs",
+            "MyMod.sln",
+            "Textures/Icon.png",
+            "Textures/Icon.psd",
+            "Art/Model.blend",
+            "dist/MyMod-1.0.zip",
+            "Textures/dist/Icon.png",
+        ] {
+            let path = root.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
         }
-    } else if entry.file_type().map_or(false, |ft| ft.is_file()) {
-        if let Some(parent) = target_path.parent() {
-            fs::create_dir_all(parent)?;
-        }
-        fs::copy(source_path, &target_path)?;
-        debug!("Copied file: {}", relative_path.display());
+        fs::write(root.path().join(PUBLISH_IGNORE_FILE_NAME), "!*.blend\n").unwrap();
+
+        let (tree, any_err) = SourceTree::collect(root.path()).unwrap();
+        assert!(!any_err);
+        assert_eq!(
+            tree.files,
+            vec![
+                PathBuf::from("About/About.xml"),
+                PathBuf::from("Art/Model.blend"),
+                PathBuf::from("Textures/Icon.png"),
+                PathBuf::from("Textures/dist/Icon.png"),
+            ]
+        );
     }
 
-    Ok(())
-}
+    #[test]
+    fn test_target_bases() {
+        use clap::Parser;
 
-fn find_sln_file(working_directory: &Path) -> Result<Option<PathBuf>> {
-    Ok(fs::read_dir(working_directory)?
-        .filter_map(Result::ok)
-        .map(|entry| entry.path())
-        .find(|path| path.is_file() && path.extension().map_or(false, |ext| ext == "sln")))
-}
+        use crate::cli::{Cli, Command, TargetConf};
+
+        let args =
+            |argv: &[&str]| match Cli::try_parse_from(["rimpub", "publish"].iter().chain(argv))
+                .unwrap()
+                .command
+            {
+                Some(Command::Publish(args)) => args,
+                command => panic!("Unexpected command: {:?}", command),
+            };
+        let mut config = Config {
+            path_game: Some(PathBuf::from("/game")),
+            ..Default::default()
+        };
 
-fn execute_dotnet_build(sln_file: &Path) -> Result<()> {
-    let output = Command::new("dotnet")
-        .arg("build")
-        .arg(sln_file)
-        .arg("--configuration")
-        .arg("Release")
-        .current_dir(sln_file.parent().unwrap_or(sln_file))
-        .output()
-        .map_err(|e| anyhow!("Failed to execute dotnet build:\n{}", e))?;
-
-    if output.status.success() {
-        info!("Project build completed successfully");
-        debug!("Build output:\n{}", decode_out(&output.stdout));
-    } else {
-        let stderr = decode_out(&output.stderr);
-        warn!("Project build failed: {}", stderr);
-        return Err(anyhow!("Project build failed:\n{}", stderr));
+        assert_eq!(
+            args(&[]).target_bases(&config).unwrap(),
+            [(None, PathBuf::from("/game/Mods"))]
+        );
+        assert_eq!(
+            args(&["--target-dir", "/mods"])
+                .target_bases(&config)
+                .unwrap(),
+            [(None, PathBuf::from("/mods"))]
+        );
+        assert!(args(&["--all-targets"]).target_bases(&config).is_err());
+
+        for (name, path) in [("stable", "/g1"), ("unstable", "/g2"), ("copy", "/g1")] {
+            config.targets.insert(
+                name.to_string(),
+                TargetConf {
+                    path_game: PathBuf::from(path),
+                },
+            );
+        }
+        // Targets pointing at the same install are only published once
+        assert_eq!(
+            args(&["-t", "stable,unstable,copy"])
+                .target_bases(&config)
+                .unwrap(),
+            [
+                (Some("stable".to_string()), PathBuf::from("/g1/Mods")),
+                (Some("unstable".to_string()), PathBuf::from("/g2/Mods")),
+            ]
+        );
+        assert_eq!(
+            args(&["--all-targets"]).target_bases(&config).unwrap(),
+            [
+                (Some("copy".to_string()), PathBuf::from("/g1/Mods")),
+                (Some("unstable".to_string()), PathBuf::from("/g2/Mods")),
+            ]
+        );
+        let err = args(&["-t", "beta"]).target_bases(&config).unwrap_err();
+        assert!(err.to_string().contains("Unknown target 'beta'"));
+        assert!(err.to_string().contains("copy, stable, unstable"));
     }
 
-    Ok(())
+    #[test]
+    fn test_copy_read_only_file() {
+        let root = tempfile::tempdir().unwrap();
+        let source = root.path().join("Readonly.txt");
+        let target = root.path().join("out/Readonly.txt");
+        fs::write(&source, "old").unwrap();
+        let mut permissions = fs::metadata(&source).unwrap().permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&source, permissions).unwrap();
+
+        copy_file(&source, &target).unwrap();
+        let target_meta = fs::metadata(&target).unwrap();
+        assert!(target_meta.permissions().readonly());
+        assert_eq!(
+            target_meta.modified().unwrap(),
+            fs::metadata(&source).unwrap().modified().unwrap()
+        );
+
+        // Updating a read-only target, as a sync does
+        let mut permissions = fs::metadata(&source).unwrap().permissions();
+        #[allow(clippy::permissions_set_readonly_false)]
+        permissions.set_readonly(false);
+        fs::set_permissions(&source, permissions).unwrap();
+        fs::write(&source, "new").unwrap();
+        copy_file(&source, &target).unwrap();
+        assert_eq!(fs::read_to_string(&target).unwrap(), "new");
+    }
 }
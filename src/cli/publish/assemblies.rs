@@ -0,0 +1,149 @@
+use std::path::Path;
+
+use log::{info, warn};
+
+use super::SourceTree;
+use crate::cli::{AssembliesConf, ReferenceAction};
+
+/// Assemblies provided by the game, Unity, Harmony or the .NET runtime, which
+/// break other mods when shipped again. The runtime is listed by name, as mods
+/// legitimately ship other `System.*` and `Mono.*` libraries.
+const DEFAULT_REFERENCES: &[&str] = &[
+    "0Harmony.dll",
+    "Assembly-CSharp.dll",
+    "Assembly-CSharp-firstpass.dll",
+    "UnityEngine.dll",
+    "UnityEngine.*.dll",
+    "Unity.*.dll",
+    "mscorlib.dll",
+    "netstandard.dll",
+    "System.dll",
+    "System.ComponentModel.Composition.dll",
+    "System.Configuration.dll",
+    "System.Core.dll",
+    "System.Data.dll",
+    "System.Drawing.dll",
+    "System.IO.Compression.dll",
+    "System.IO.Compression.FileSystem.dll",
+    "System.Net.Http.dll",
+    "System.Numerics.dll",
+    "System.Runtime.Serialization.dll",
+    "System.Security.dll",
+    "System.ServiceModel.Internals.dll",
+    "System.Transactions.dll",
+    "System.Xml.dll",
+    "System.Xml.Linq.dll",
+    "Mono.Posix.dll",
+    "Mono.Security.dll",
+    "*.pdb",
+];
+
+/// Finds reference-only assemblies in `Assemblies` folders.
+pub struct ReferenceFilter<'a> {
+    conf: &'a AssembliesConf,
+}
+
+impl<'a> ReferenceFilter<'a> {
+    pub fn new(conf: &'a AssembliesConf) -> Self {
+        ReferenceFilter { conf }
+    }
+
+    pub fn is_reference(&self, relative: &Path) -> bool {
+        let in_assemblies = relative
+            .parent()
+            .and_then(Path::file_name)
+            .is_some_and(|name| name.eq_ignore_ascii_case("Assemblies"));
+        let Some(name) = relative.file_name().and_then(|name| name.to_str()) else {
+            return false;
+        };
+
+        in_assemblies
+            && !self.conf.allow.iter().any(|p| wildcard_match(p, name))
+            && DEFAULT_REFERENCES
+                .iter()
+                .copied()
+                .chain(self.conf.deny.iter().map(String::as_str))
+                .any(|p| wildcard_match(p, name))
+    }
+
+    /// Reports reference assemblies in `source`, dropping them unless
+    /// configured to only warn.
+    pub fn apply(&self, source: &mut SourceTree) {
+        if self.conf.references == ReferenceAction::Off {
+            return;
+        }
+
+        let exclude = self.conf.references == ReferenceAction::Exclude;
+        source.retain(|path, is_dir| {
+            if is_dir || !self.is_reference(path) {
+                return true;
+            }
+            if exclude {
+                info!("Excluding reference assembly: {}", path.display());
+            } else {
+                warn!("Reference assembly will be published: {}", path.display());
+            }
+            !exclude
+        });
+    }
+}
+
+/// Case-insensitive match where `*` stands for any run of characters.
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.to_ascii_lowercase();
+    let name = name.to_ascii_lowercase();
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts = parts.collect::<Vec<_>>();
+    let Some((last, middle)) = parts.split_last() else {
+        // No wildcard at all
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(idx) => rest = &rest[idx + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match(
+            "UnityEngine.*.dll",
+            "UnityEngine.CoreModule.dll"
+        ));
+        assert!(wildcard_match("*.pdb", "MyMod.PDB"));
+        assert!(wildcard_match("0Harmony.dll", "0harmony.dll"));
+        assert!(!wildcard_match("UnityEngine.*.dll", "UnityEngine.dll"));
+        assert!(!wildcard_match("System.dll", "System.Core.dll"));
+    }
+
+    #[test]
+    fn test_reference_filter() {
+        let conf = AssembliesConf {
+            allow: vec!["0Harmony.dll".into()],
+            deny: vec!["HugsLib.dll".into()],
+            ..Default::default()
+        };
+        let filter = ReferenceFilter::new(&conf);
+        assert!(filter.is_reference(Path::new("1.5/Assemblies/UnityEngine.IMGUIModule.dll")));
+        assert!(filter.is_reference(Path::new("Assemblies/MyMod.pdb")));
+        assert!(filter.is_reference(Path::new("Assemblies/HugsLib.dll")));
+        assert!(!filter.is_reference(Path::new("Assemblies/0Harmony.dll")));
+        assert!(!filter.is_reference(Path::new("Assemblies/MyMod.dll")));
+        assert!(filter.is_reference(Path::new("Assemblies/System.Xml.Linq.dll")));
+        assert!(!filter.is_reference(Path::new("Assemblies/System.Text.Json.dll")));
+        assert!(!filter.is_reference(Path::new("Assemblies/Mono.Cecil.dll")));
+        assert!(!filter.is_reference(Path::new("Source/Lib/System.dll")));
+    }
+}
@@ -0,0 +1,192 @@
+use std::{
+    io::{BufRead, BufReader, Read},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    thread,
+    time::SystemTime,
+};
+
+use anyhow::{Result, anyhow};
+use ignore::WalkBuilder;
+use log::{debug, info, warn};
+
+use super::{
+    diagnostics::{BuildReport, Diagnostic},
+    verify::verify_assemblies,
+};
+use crate::{cli::BuildConf, util::decode_out};
+
+/// Runs the build step configured in `[build]`, defaulting to `dotnet build` of
+/// every solution found under the working directory.
+pub fn run_build(working_directory: &Path, conf: &BuildConf) -> Result<BuildReport> {
+    let build_start = SystemTime::now();
+
+    if let Some((program, args)) = conf.command.split_first() {
+        info!("Executing custom build command: {}", conf.command.join(" "));
+        let mut command = Command::new(program);
+        command.args(args).current_dir(working_directory);
+        let report = execute(command, conf)?;
+        verify_assemblies(working_directory, conf, build_start)?;
+        return Ok(report);
+    }
+
+    let mut report = BuildReport::default();
+
+    let projects = match &conf.project {
+        Some(project) => {
+            let project = working_directory.join(project);
+            if !project.is_file() {
+                return Err(anyhow!(
+                    "Configured build project does not exist: {}",
+                    project.display()
+                ));
+            }
+            vec![project]
+        },
+        None => find_files(working_directory, conf.search_depth, "sln", true),
+    };
+
+    match projects.len() {
+        0 => {
+            debug!("No solution file found, skipping build");
+            return Ok(report);
+        },
+        1 => {},
+        n => info!("Found {} solution files, building each of them", n),
+    }
+
+    for project in projects {
+        info!("Executing build: {}", project.display());
+        let mut command = Command::new("dotnet");
+        command
+            .arg("build")
+            .arg(&project)
+            .arg("--configuration")
+            .arg(&conf.configuration)
+            .args(&conf.args)
+            .current_dir(project.parent().unwrap_or(&project));
+        report.merge(execute(command, conf)?);
+    }
+
+    verify_assemblies(working_directory, conf, build_start)?;
+    Ok(report)
+}
+
+/// Finds files with `extension` up to `depth` levels below `root`, skipping
+/// build output folders and optionally git ignored files.
+pub(crate) fn find_files(
+    root: &Path,
+    depth: usize,
+    extension: &str,
+    git_ignore: bool,
+) -> Vec<PathBuf> {
+    let mut builder = WalkBuilder::new(root);
+    builder
+        .max_depth(Some(depth))
+        .git_ignore(git_ignore)
+        .git_exclude(git_ignore)
+        .git_global(git_ignore)
+        .filter_entry(|entry| !matches!(entry.file_name().to_str(), Some("bin" | "obj")));
+
+    let mut files = builder
+        .build()
+        .filter_map(|result| {
+            result
+                .inspect_err(|e| debug!("Error reading entry: {}", e))
+                .ok()
+        })
+        .filter(|entry| entry.file_type().is_some_and(|ft| ft.is_file()))
+        .map(|entry| entry.into_path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == extension))
+        .collect::<Vec<_>>();
+    files.sort();
+    files
+}
+
+/// Runs `command`, streaming its output to the log while collecting MSBuild
+/// diagnostics.
+fn execute(mut command: Command, conf: &BuildConf) -> Result<BuildReport> {
+    let program = command.get_program().to_string_lossy().to_string();
+    let mut child = command
+        .envs(&conf.env)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow!("Failed to execute {}:\n{}", program, e))?;
+
+    let stderr = child.stderr.take().expect("stderr should be piped");
+    let stderr_reader = thread::spawn(move || stream_lines(stderr, |line| warn!("{}", line)));
+    let mut report = stream_lines(
+        child.stdout.take().expect("stdout should be piped"),
+        |line| info!("{}", line),
+    );
+    report.merge(stderr_reader.join().expect("Build output reader panicked"));
+
+    let status = child
+        .wait()
+        .map_err(|e| anyhow!("Failed to wait for {}: {}", program, e))?;
+    if !status.success() {
+        report.print_summary();
+        return Err(anyhow!("Project build failed: {}", status));
+    }
+
+    info!("Project build completed successfully");
+    Ok(report)
+}
+
+fn stream_lines(reader: impl Read, log_line: impl Fn(&str)) -> BuildReport {
+    let mut report = BuildReport::default();
+    let mut reader = BufReader::new(reader);
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        match reader.read_until(b'\n', &mut buf) {
+            Ok(0) => break,
+            Ok(_) => {
+                let line = decode_out(&buf);
+                let line = line.trim_end();
+                log_line(line);
+                if let Some(diagnostic) = Diagnostic::parse(line) {
+                    report.add(diagnostic);
+                }
+            },
+            Err(e) => {
+                warn!("Failed to read build output: {}", e);
+                break;
+            },
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn test_find_files() {
+        let root = tempfile::tempdir().unwrap();
+        for path in [
+            "Source/ModA/ModA.sln",
+            "1.5/Source/ModB/ModB.sln",
+            "Source/ModA/obj/Nested.sln",
+            "Source/ModA/ModA.csproj",
+        ] {
+            let path = root.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+
+        let found = find_files(root.path(), 4, "sln", true);
+        assert_eq!(
+            found,
+            vec![
+                root.path().join("1.5/Source/ModB/ModB.sln"),
+                root.path().join("Source/ModA/ModA.sln"),
+            ]
+        );
+        assert_eq!(find_files(root.path(), 3, "sln", true).len(), 1);
+    }
+}
@@ -0,0 +1,186 @@
+use std::collections::BTreeMap;
+
+use log::{error, info, warn};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A single `file(line,col): error CODE: message` line from MSBuild output.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Diagnostic {
+    /// Source file, or the reporting tool such as `MSBUILD` or `CSC`
+    pub origin: String,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+    pub severity: Severity,
+    pub code: String,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn parse(line: &str) -> Option<Self> {
+        let line = line.trim();
+        let (origin, severity, rest) = [
+            (": error ", Severity::Error),
+            (": warning ", Severity::Warning),
+        ]
+        .into_iter()
+        .find_map(|(marker, severity)| {
+            line.split_once(marker)
+                .map(|(origin, rest)| (origin, severity, rest))
+        })?;
+
+        let (code, message) = rest.split_once(": ")?;
+        if code.is_empty() || code.contains(char::is_whitespace) {
+            return None;
+        }
+        // MSBuild appends the project being built as ` [path]`
+        let message = match message.rfind(" [") {
+            Some(idx) if message.ends_with(']') => &message[..idx],
+            _ => message,
+        };
+
+        let (origin, line, column) = match origin.strip_suffix(')').and_then(|o| o.rsplit_once('('))
+        {
+            Some((file, position)) => {
+                let mut numbers = position.split(',').map(|n| n.trim().parse().ok());
+                (file, numbers.next().flatten(), numbers.next().flatten())
+            },
+            None => (origin, None, None),
+        };
+
+        Some(Diagnostic {
+            origin: origin.trim().to_string(),
+            line,
+            column,
+            severity,
+            code: code.to_string(),
+            message: message.trim().to_string(),
+        })
+    }
+}
+
+/// Diagnostics collected over one or more builds, deduplicated since MSBuild
+/// repeats them in its closing summary.
+#[derive(Debug, Default)]
+pub struct BuildReport {
+    diagnostics: BTreeMap<String, Vec<Diagnostic>>,
+}
+
+impl BuildReport {
+    pub fn add(&mut self, diagnostic: Diagnostic) {
+        let entries = self
+            .diagnostics
+            .entry(diagnostic.origin.clone())
+            .or_default();
+        if !entries.contains(&diagnostic) {
+            entries.push(diagnostic);
+        }
+    }
+
+    pub fn merge(&mut self, other: BuildReport) {
+        for diagnostic in other.diagnostics.into_values().flatten() {
+            self.add(diagnostic);
+        }
+    }
+
+    pub fn count(&self, severity: Severity) -> usize {
+        self.diagnostics
+            .values()
+            .flatten()
+            .filter(|d| d.severity == severity)
+            .count()
+    }
+
+    pub fn print_summary(&self) {
+        let errors = self.count(Severity::Error);
+        let warnings = self.count(Severity::Warning);
+        let summary = format!(
+            "Build finished with {} error(s), {} warning(s)",
+            errors, warnings
+        );
+        if errors > 0 {
+            error!("{}", summary);
+        } else if warnings > 0 {
+            warn!("{}", summary);
+        } else {
+            info!("{}", summary);
+        }
+
+        for (origin, diagnostics) in &self.diagnostics {
+            let count = |severity| {
+                diagnostics
+                    .iter()
+                    .filter(|d| d.severity == severity)
+                    .count()
+            };
+            info!(
+                "{}: {} error(s), {} warning(s)",
+                origin,
+                count(Severity::Error),
+                count(Severity::Warning)
+            );
+            let mut diagnostics = diagnostics.iter().collect::<Vec<_>>();
+            diagnostics.sort_by_key(|d| (d.severity, d.line, d.column));
+            for d in diagnostics {
+                let position = match (d.line, d.column) {
+                    (Some(line), Some(column)) => format!("{}:{} ", line, column),
+                    (Some(line), None) => format!("{} ", line),
+                    _ => String::new(),
+                };
+                match d.severity {
+                    Severity::Error => error!("  {}{}: {}", position, d.code, d.message),
+                    Severity::Warning => warn!("  {}{}: {}", position, d.code, d.message),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_diagnostic() {
+        let d = Diagnostic::parse(
+            r"  C:\Mods\Foo\Source\Foo.cs(12,34): error CS0103: The name 'bar' does not exist in the current context [C:\Mods\Foo\Source\Foo.csproj]",
+        )
+        .unwrap();
+        assert_eq!(d.origin, r"C:\Mods\Foo\Source\Foo.cs");
+        assert_eq!((d.line, d.column), (Some(12), Some(34)));
+        assert_eq!(d.severity, Severity::Error);
+        assert_eq!(d.code, "CS0103");
+        assert_eq!(
+            d.message,
+            "The name 'bar' does not exist in the current context"
+        );
+
+        let d = Diagnostic::parse("MSBUILD : error MSB1009: Project file does not exist.").unwrap();
+        assert_eq!((d.origin.as_str(), d.line), ("MSBUILD", None));
+
+        let d = Diagnostic::parse("/src/A.cs(3,1,3,9): warning CS0168: Unused [/src/A.csproj]")
+            .unwrap();
+        assert_eq!(
+            (d.severity, d.line, d.column),
+            (Severity::Warning, Some(3), Some(1))
+        );
+
+        assert!(Diagnostic::parse("Build succeeded.").is_none());
+        assert!(Diagnostic::parse("    0 Warning(s)").is_none());
+    }
+
+    #[test]
+    fn test_report_dedup() {
+        let line = "/src/A.cs(3,1): warning CS0168: Unused";
+        let mut report = BuildReport::default();
+        report.add(Diagnostic::parse(line).unwrap());
+        report.add(Diagnostic::parse(line).unwrap());
+        report.add(Diagnostic::parse("/src/B.cs(1,1): error CS1002: ; expected").unwrap());
+        assert_eq!(report.count(Severity::Warning), 1);
+        assert_eq!(report.count(Severity::Error), 1);
+    }
+}
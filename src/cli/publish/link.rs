@@ -0,0 +1,148 @@
+use std::{fs, io, path::Path};
+
+use anyhow::{Result, anyhow};
+use log::{debug, info};
+
+use crate::util::confirm;
+
+/// Points `target_path` at `source_root` with a symlink, or a junction on
+/// Windows, replacing a previously copied folder.
+pub fn link_dir(source_root: &Path, target_path: &Path, no_ask: bool, dry_run: bool) -> Result<()> {
+    let source_root = source_root.canonicalize()?;
+
+    match fs::symlink_metadata(target_path) {
+        Ok(meta) if meta.file_type().is_symlink() => {
+            let current = fs::read_link(target_path)?;
+            // Relative links resolve against the directory holding them
+            let resolved = target_path
+                .parent()
+                .map(|parent| parent.join(&current))
+                .unwrap_or_else(|| current.clone());
+            if resolved
+                .canonicalize()
+                .is_ok_and(|path| path == source_root)
+            {
+                info!("Already linked: {}", target_path.display());
+                return Ok(());
+            }
+            return Err(anyhow!(
+                "'{}' is a link to '{}', not to this project, remove it manually first",
+                target_path.display(),
+                current.display()
+            ));
+        },
+        Ok(meta) if meta.is_dir() => {
+            if dry_run {
+                info!("Would replace copied folder: {}", target_path.display());
+            } else {
+                if !no_ask
+                    && !confirm(&format!(
+                        "Target directory '{}' is a copy. Do you want to delete it and link instead? (y/N): ",
+                        target_path.display()
+                    ))
+                {
+                    info!("Skipped {} as cancelled by user", target_path.display());
+                    return Ok(());
+                }
+                debug!("Removing copied folder: {}", target_path.display());
+                fs::remove_dir_all(target_path)
+                    .map_err(|e| anyhow!("Failed to remove existing target directory: {}", e))?;
+            }
+        },
+        Ok(_) => {
+            return Err(anyhow!(
+                "'{}' exists and is not a directory",
+                target_path.display()
+            ));
+        },
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {},
+        Err(e) => return Err(e.into()),
+    }
+
+    if dry_run {
+        info!(
+            "Would link: {} -> {}",
+            target_path.display(),
+            source_root.display()
+        );
+        return Ok(());
+    }
+
+    if let Some(parent) = target_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    create_link(&source_root, target_path)
+        .map_err(|e| anyhow!("Failed to link {}: {}", target_path.display(), e))?;
+    info!(
+        "Linked: {} -> {}",
+        target_path.display(),
+        source_root.display()
+    );
+    Ok(())
+}
+
+#[cfg(not(target_os = "windows"))]
+fn create_link(source: &Path, link: &Path) -> Result<()> {
+    std::os::unix::fs::symlink(source, link)?;
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn create_link(source: &Path, link: &Path) -> Result<()> {
+    use std::process::Command;
+
+    use crate::util::decode_out;
+
+    // Symlinks need developer mode or elevation, junctions do not
+    if std::os::windows::fs::symlink_dir(source, link).is_ok() {
+        return Ok(());
+    }
+    let output = Command::new("cmd")
+        .arg("/C")
+        .arg("mklink")
+        .arg("/J")
+        .arg(link)
+        .arg(source)
+        .output()?;
+    if !output.status.success() {
+        return Err(anyhow!("{}", decode_out(&output.stderr).trim()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_link_dir() {
+        let root = tempfile::tempdir().unwrap();
+        let source = root.path().join("Project");
+        let other = root.path().join("Other");
+        let target = root.path().join("Mods/MyMod");
+        fs::create_dir_all(source.join("About")).unwrap();
+        fs::create_dir_all(&other).unwrap();
+
+        // A copied folder is replaced by the link
+        fs::create_dir_all(target.join("Stale")).unwrap();
+        link_dir(&source, &target, true, false).unwrap();
+        assert!(
+            fs::symlink_metadata(&target)
+                .unwrap()
+                .file_type()
+                .is_symlink()
+        );
+        assert!(target.join("About").is_dir());
+        assert!(!target.join("Stale").exists());
+
+        // Relinking the same project is a no-op
+        link_dir(&source, &target, true, false).unwrap();
+
+        // Links owned by another project are left alone
+        assert!(link_dir(&other, &target, true, false).is_err());
+        assert_eq!(
+            fs::read_link(&target).unwrap().canonicalize().unwrap(),
+            source.canonicalize().unwrap()
+        );
+    }
+}
@@ -0,0 +1,291 @@
+use std::{
+    collections::BTreeSet,
+    fs::{self, File},
+    io::{self, BufReader, Read},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use log::{debug, warn};
+
+use super::{SourceTree, copy_file, make_writable};
+
+/// How an existing target file is compared against its source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareMode {
+    /// Compare file size and modification time.
+    Metadata,
+    /// Compare the full file contents.
+    Content,
+}
+
+/// Changes needed to bring a target directory in line with a source tree.
+#[derive(Debug, Default)]
+pub struct SyncPlan {
+    pub added: Vec<PathBuf>,
+    pub updated: Vec<PathBuf>,
+    pub removed: Vec<PathBuf>,
+    pub unchanged: usize,
+    dirs_created: Vec<PathBuf>,
+    dirs_removed: Vec<PathBuf>,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SyncStats {
+    pub added: usize,
+    pub updated: usize,
+    pub removed: usize,
+    pub unchanged: usize,
+    pub failed: usize,
+}
+
+impl SyncPlan {
+    pub fn build(source: &SourceTree, target_root: &Path, mode: CompareMode) -> Result<Self> {
+        let (target_files, target_dirs) = list_tree(target_root)?;
+        let source_files = source.files.iter().collect::<BTreeSet<_>>();
+        let source_dirs = source.dirs.iter().collect::<BTreeSet<_>>();
+
+        let mut plan = SyncPlan {
+            removed: target_files
+                .iter()
+                .filter(|path| !source_files.contains(path))
+                .cloned()
+                .collect(),
+            dirs_created: source
+                .dirs
+                .iter()
+                .filter(|path| !target_dirs.contains(*path))
+                .cloned()
+                .collect(),
+            dirs_removed: target_dirs
+                .iter()
+                .rev()
+                .filter(|path| !source_dirs.contains(path))
+                .cloned()
+                .collect(),
+            ..Default::default()
+        };
+
+        for relative in &source.files {
+            if !target_files.contains(relative) {
+                plan.added.push(relative.clone());
+            } else if is_same(
+                &source.root.join(relative),
+                &target_root.join(relative),
+                mode,
+            )? {
+                plan.unchanged += 1;
+            } else {
+                plan.updated.push(relative.clone());
+            }
+        }
+
+        Ok(plan)
+    }
+
+    /// Plan for recreating the target from scratch, where every existing target
+    /// file is deleted and every source file is copied.
+    pub fn full(source: &SourceTree, target_root: &Path) -> Result<Self> {
+        let (target_files, target_dirs) = list_tree(target_root)?;
+        Ok(SyncPlan {
+            removed: target_files.into_iter().collect(),
+            dirs_removed: target_dirs.into_iter().rev().collect(),
+            ..Self::copy(source)
+        })
+    }
+
+    /// Plan for copying every source file into an empty target.
+    pub fn copy(source: &SourceTree) -> Self {
+        SyncPlan {
+            added: source.files.clone(),
+            dirs_created: source.dirs.clone(),
+            ..Default::default()
+        }
+    }
+
+    pub fn apply(&self, source_root: &Path, target_root: &Path) -> SyncStats {
+        let mut stats = SyncStats {
+            unchanged: self.unchanged,
+            ..Default::default()
+        };
+
+        for relative in &self.removed {
+            match remove_file(&target_root.join(relative)) {
+                Ok(()) => {
+                    debug!("Removed file: {}", relative.display());
+                    stats.removed += 1;
+                },
+                Err(e) => {
+                    warn!("Failed to remove {}: {}", relative.display(), e);
+                    stats.failed += 1;
+                },
+            }
+        }
+
+        // Deepest first, so only directories emptied above are removed
+        for relative in &self.dirs_removed {
+            if let Err(e) = fs::remove_dir(target_root.join(relative)) {
+                warn!("Failed to remove directory {}: {}", relative.display(), e);
+                stats.failed += 1;
+            } else {
+                debug!("Removed directory: {}", relative.display());
+            }
+        }
+
+        for relative in &self.dirs_created {
+            if let Err(e) = fs::create_dir_all(target_root.join(relative)) {
+                warn!("Failed to create directory {}: {}", relative.display(), e);
+                stats.failed += 1;
+            }
+        }
+
+        let (added, failed) = copy_all(&self.added, source_root, target_root);
+        stats.added = added;
+        stats.failed += failed;
+        let (updated, failed) = copy_all(&self.updated, source_root, target_root);
+        stats.updated = updated;
+        stats.failed += failed;
+
+        stats
+    }
+}
+
+/// Removes `path`, which Windows refuses while it is read-only.
+fn remove_file(path: &Path) -> Result<()> {
+    make_writable(path)?;
+    fs::remove_file(path)?;
+    Ok(())
+}
+
+fn copy_all(files: &[PathBuf], source_root: &Path, target_root: &Path) -> (usize, usize) {
+    let mut copied = 0;
+    let mut failed = 0;
+    for relative in files {
+        match copy_file(&source_root.join(relative), &target_root.join(relative)) {
+            Ok(()) => {
+                debug!("Copied file: {}", relative.display());
+                copied += 1;
+            },
+            Err(e) => {
+                warn!("Failed to copy {}: {}", relative.display(), e);
+                failed += 1;
+            },
+        }
+    }
+    (copied, failed)
+}
+
+fn is_same(source: &Path, target: &Path, mode: CompareMode) -> Result<bool> {
+    let source_meta = fs::metadata(source)?;
+    let target_meta = fs::symlink_metadata(target)?;
+    if !target_meta.is_file() || source_meta.len() != target_meta.len() {
+        return Ok(false);
+    }
+
+    Ok(match mode {
+        CompareMode::Metadata => match (source_meta.modified(), target_meta.modified()) {
+            (Ok(source_time), Ok(target_time)) => source_time == target_time,
+            _ => false,
+        },
+        CompareMode::Content => same_content(source, target)?,
+    })
+}
+
+fn same_content(a: &Path, b: &Path) -> io::Result<bool> {
+    const CHUNK: usize = 64 * 1024;
+
+    let mut reader_a = BufReader::new(File::open(a)?);
+    let mut reader_b = BufReader::new(File::open(b)?);
+    let mut buf_a = vec![0; CHUNK];
+    let mut buf_b = vec![0; CHUNK];
+
+    loop {
+        let read = reader_a.read(&mut buf_a)?;
+        if read == 0 {
+            // Lengths were checked to be equal beforehand
+            return Ok(true);
+        }
+        reader_b.read_exact(&mut buf_b[..read])?;
+        if buf_a[..read] != buf_b[..read] {
+            return Ok(false);
+        }
+    }
+}
+
+/// Lists all files and directories under `root` as relative paths, without
+/// following symlinks.
+fn list_tree(root: &Path) -> io::Result<(BTreeSet<PathBuf>, BTreeSet<PathBuf>)> {
+    let mut files = BTreeSet::new();
+    let mut dirs = BTreeSet::new();
+    if !root.exists() {
+        return Ok((files, dirs));
+    }
+
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            let relative = path
+                .strip_prefix(root)
+                .expect("Entry should be under root")
+                .to_path_buf();
+            if entry.file_type()?.is_dir() {
+                dirs.insert(relative);
+                pending.push(path);
+            } else {
+                files.insert(relative);
+            }
+        }
+    }
+
+    Ok((files, dirs))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sync(source: &Path, target: &Path, mode: CompareMode) -> SyncStats {
+        let (tree, any_err) = SourceTree::collect(source).unwrap();
+        assert!(!any_err);
+        SyncPlan::build(&tree, target, mode)
+            .unwrap()
+            .apply(source, target)
+    }
+
+    #[test]
+    fn test_sync_only_touches_changed_files() {
+        let source = tempfile::tempdir().unwrap();
+        let target = tempfile::tempdir().unwrap();
+        fs::create_dir_all(source.path().join("About")).unwrap();
+        fs::write(source.path().join("About/About.xml"), "<ModMetaData/>").unwrap();
+        fs::write(source.path().join("Preview.png"), "png").unwrap();
+
+        let stats = sync(source.path(), target.path(), CompareMode::Metadata);
+        assert_eq!((stats.added, stats.updated, stats.removed), (2, 0, 0));
+
+        let stats = sync(source.path(), target.path(), CompareMode::Metadata);
+        assert_eq!((stats.added, stats.updated, stats.unchanged), (0, 0, 2));
+
+        fs::write(source.path().join("Preview.png"), "png2").unwrap();
+        fs::remove_dir_all(source.path().join("About")).unwrap();
+        let stats = sync(source.path(), target.path(), CompareMode::Content);
+        assert_eq!((stats.updated, stats.removed, stats.failed), (1, 1, 0));
+        assert!(!target.path().join("About").exists());
+        assert_eq!(
+            fs::read_to_string(target.path().join("Preview.png")).unwrap(),
+            "png2"
+        );
+
+        // Stale files copied read-only are still removed
+        let stale = target.path().join("Stale.xml");
+        fs::write(&stale, "<Defs/>").unwrap();
+        let mut permissions = fs::metadata(&stale).unwrap().permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&stale, permissions).unwrap();
+        let stats = sync(source.path(), target.path(), CompareMode::Metadata);
+        assert_eq!((stats.removed, stats.failed), (1, 0));
+        assert!(!stale.exists());
+    }
+}
@@ -0,0 +1,221 @@
+use std::{fs, path::Path, time::SystemTime};
+
+use anyhow::{Result, anyhow};
+use ignore::WalkBuilder;
+use log::{debug, error, info, warn};
+use roxmltree::Document;
+
+use super::build::find_files;
+use crate::cli::{AssemblyCheck, BuildConf};
+
+/// Build output of a single `.csproj`.
+#[derive(Debug)]
+struct BuiltAssembly {
+    name: String,
+    output_path: Option<String>,
+    /// Newest intermediate build of the assembly under `obj/`
+    intermediate: Option<SystemTime>,
+}
+
+/// Checks that each assembly built into an `Assemblies` folder by the
+/// project's `.csproj` files has an up-to-date copy there.
+pub fn verify_assemblies(
+    working_directory: &Path,
+    conf: &BuildConf,
+    build_start: SystemTime,
+) -> Result<()> {
+    if conf.check_assemblies == AssemblyCheck::Off {
+        return Ok(());
+    }
+
+    let assemblies = find_files(working_directory, conf.search_depth, "csproj", true)
+        .iter()
+        .filter_map(|csproj| {
+            read_assembly(csproj)
+                .inspect_err(|e| warn!("Failed to read {}: {}", csproj.display(), e))
+                .ok()
+        })
+        // Test and tooling projects are built but never shipped
+        .filter(|assembly| {
+            let shipped = assembly.output_path.as_deref().is_some_and(is_assemblies_path);
+            if !shipped {
+                debug!("Not verifying {}, it is not built into Assemblies", assembly.name);
+            }
+            shipped
+        })
+        .collect::<Vec<_>>();
+    // Built assemblies are often git ignored
+    let published = find_files(working_directory, conf.search_depth, "dll", false)
+        .into_iter()
+        .filter(|path| {
+            path.parent()
+                .and_then(Path::file_name)
+                .is_some_and(|name| name == "Assemblies")
+        })
+        .collect::<Vec<_>>();
+
+    let mut problems = Vec::new();
+    for assembly in &assemblies {
+        let file_name = format!("{}.dll", assembly.name);
+        let copies = published
+            .iter()
+            .filter(|path| {
+                path.file_name()
+                    .is_some_and(|name| name == file_name.as_str())
+            })
+            .filter_map(|path| Some((path, fs::metadata(path).ok()?.modified().ok()?)))
+            .collect::<Vec<_>>();
+
+        // An up-to-date build leaves the output untouched, so compare against
+        // the freshly compiled intermediate when there is one
+        let reference = assembly.intermediate.unwrap_or(build_start);
+        let hint = assembly
+            .output_path
+            .as_deref()
+            .map(|path| format!(", check <OutputPath> '{}'", path))
+            .unwrap_or_default();
+
+        match copies.iter().find(|(_, modified)| *modified >= reference) {
+            Some((path, _)) => debug!("Verified assembly: {}", path.display()),
+            None if copies.is_empty() => problems.push(format!(
+                "{} was not found in any Assemblies folder{}",
+                file_name, hint
+            )),
+            None => problems.push(format!(
+                "{} in {} is older than the build output{}",
+                file_name,
+                copies
+                    .iter()
+                    .map(|(path, _)| path
+                        .strip_prefix(working_directory)
+                        .unwrap_or(path)
+                        .display()
+                        .to_string())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                hint
+            )),
+        }
+    }
+
+    if problems.is_empty() {
+        if !assemblies.is_empty() {
+            info!("Verified {} built assembly(s)", assemblies.len());
+        }
+        return Ok(());
+    }
+
+    match conf.check_assemblies {
+        AssemblyCheck::Error => {
+            for problem in &problems {
+                error!("{}", problem);
+            }
+            Err(anyhow!(
+                "{} assembly(s) failed verification, stale DLLs would be published",
+                problems.len()
+            ))
+        },
+        _ => {
+            for problem in &problems {
+                warn!("{}", problem);
+            }
+            Ok(())
+        },
+    }
+}
+
+/// Whether an `<OutputPath>` leads into an `Assemblies` folder.
+fn is_assemblies_path(output_path: &str) -> bool {
+    output_path
+        .split(['/', '\\'])
+        .any(|segment| segment.eq_ignore_ascii_case("Assemblies"))
+}
+
+fn read_assembly(csproj: &Path) -> Result<BuiltAssembly> {
+    let content = fs::read_to_string(csproj)?;
+    let doc = Document::parse(&content)?;
+    let property = |tag: &str| {
+        doc.descendants()
+            .find(|n| n.tag_name().name() == tag)
+            .and_then(|n| n.text())
+            .map(|text| text.trim().to_string())
+            .filter(|text| !text.is_empty())
+    };
+
+    let name = property("AssemblyName").unwrap_or_else(|| {
+        csproj
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default()
+    });
+    let file_name = format!("{}.dll", name);
+    let intermediate = csproj.parent().and_then(|dir| {
+        WalkBuilder::new(dir.join("obj"))
+            .standard_filters(false)
+            .build()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_name() == file_name.as_str())
+            .filter_map(|entry| entry.metadata().ok()?.modified().ok())
+            .max()
+    });
+
+    Ok(BuiltAssembly {
+        name,
+        output_path: property("OutputPath"),
+        intermediate,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs::File, time::Duration};
+
+    use super::*;
+
+    fn write_at(path: &Path, modified: SystemTime) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "").unwrap();
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_verify_assemblies() {
+        let root = tempfile::tempdir().unwrap();
+        let conf = BuildConf {
+            check_assemblies: AssemblyCheck::Error,
+            ..Default::default()
+        };
+        let build_start = SystemTime::now();
+        let earlier = build_start - Duration::from_secs(60);
+
+        fs::create_dir_all(root.path().join("Source/MyMod")).unwrap();
+        fs::write(
+            root.path().join("Source/MyMod/MyMod.csproj"),
+            r#"<Project Sdk="Microsoft.NET.Sdk"><PropertyGroup><AssemblyName>Foo</AssemblyName><OutputPath>..\..\1.5\Assemblies\</OutputPath></PropertyGroup></Project>"#,
+        )
+        .unwrap();
+        // Never shipped, so never expected in Assemblies
+        fs::create_dir_all(root.path().join("Source/MyMod.Tests")).unwrap();
+        fs::write(
+            root.path().join("Source/MyMod.Tests/MyMod.Tests.csproj"),
+            r#"<Project Sdk="Microsoft.NET.Sdk"><PropertyGroup><OutputPath>bin/</OutputPath></PropertyGroup></Project>"#,
+        )
+        .unwrap();
+        assert!(verify_assemblies(root.path(), &conf, build_start).is_err());
+
+        write_at(&root.path().join("1.5/Assemblies/Foo.dll"), earlier);
+        assert!(verify_assemblies(root.path(), &conf, build_start).is_err());
+
+        // Up-to-date builds only leave an equally old intermediate behind
+        write_at(
+            &root.path().join("Source/MyMod/obj/Release/Foo.dll"),
+            earlier,
+        );
+        assert!(verify_assemblies(root.path(), &conf, build_start).is_ok());
+    }
+}
@@ -0,0 +1,259 @@
+use std::{fmt, fs, path::Path};
+
+use anyhow::{Result, anyhow};
+use clap::{Args, Subcommand, ValueEnum};
+use log::{debug, info, warn};
+
+use super::{PROJECT_CONFIG_FILE_NAME, ProjectConf, publish::find_files};
+use crate::about::{ABOUT_FILE_PATH, set_mod_version};
+
+#[derive(Debug, Args)]
+pub struct VersionArgs {
+    #[command(subcommand)]
+    pub command: VersionCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum VersionCommand {
+    /// Increase the mod version and write it to About/About.xml
+    Bump(VersionBumpArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct VersionBumpArgs {
+    /// Which part of the version to increase
+    #[arg(value_enum, default_value_t = BumpPart::Patch)]
+    pub part: BumpPart,
+
+    /// Also update the assembly version in .csproj and AssemblyInfo.cs files
+    #[arg(long)]
+    pub assembly: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum BumpPart {
+    Major,
+    Minor,
+    Patch,
+}
+
+impl VersionArgs {
+    pub fn run(&self) -> Result<()> {
+        match &self.command {
+            VersionCommand::Bump(args) => args.run(),
+        }
+    }
+}
+
+impl VersionBumpArgs {
+    pub fn run(&self) -> Result<()> {
+        let working_directory = std::env::current_dir()?;
+        let (config, _) = ProjectConf::load_current()?;
+
+        let current = match config.resolve_version(&working_directory) {
+            Some(version) => Version::parse(&version)?,
+            None => {
+                warn!(
+                    "No version in {} or {}, starting from 0.0.0",
+                    PROJECT_CONFIG_FILE_NAME, ABOUT_FILE_PATH
+                );
+                Version::default()
+            },
+        };
+        let next = current.bump(self.part);
+        let version = next.to_string();
+
+        // Both files are updated only once both edits succeeded, so a broken
+        // About.xml does not leave the versions out of sync
+        let project_config = ProjectConf::with_version(&working_directory, &version)?;
+        let about_path = working_directory.join(ABOUT_FILE_PATH);
+        let about = match fs::read_to_string(&about_path) {
+            Ok(content) => Some(
+                set_mod_version(&content, &version)
+                    .map_err(|e| anyhow!("Failed to update {}: {}", ABOUT_FILE_PATH, e))?,
+            ),
+            Err(e) => {
+                warn!("Failed to update {}: {}", ABOUT_FILE_PATH, e);
+                None
+            },
+        };
+
+        fs::write(
+            working_directory.join(PROJECT_CONFIG_FILE_NAME),
+            project_config,
+        )?;
+        if let Some(about) = about {
+            fs::write(&about_path, about)?;
+        }
+
+        if self.assembly {
+            update_assembly_versions(&working_directory, config.build.search_depth, &version)?;
+        }
+
+        info!("Bumped version: {} -> {}", current, next);
+        Ok(())
+    }
+}
+
+/// A `major.minor.patch` version, where missing parts count as 0.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+impl Version {
+    pub fn parse(text: &str) -> Result<Self> {
+        let text = text.trim();
+        let invalid = || anyhow!("Invalid version '{}', expected 'major.minor.patch'", text);
+        let mut parts = text
+            .strip_prefix('v')
+            .unwrap_or(text)
+            .split('.')
+            .map(|part| part.parse::<u64>().map_err(|_| invalid()));
+        let version = Version {
+            major: parts.next().ok_or_else(invalid)??,
+            minor: parts.next().transpose()?.unwrap_or(0),
+            patch: parts.next().transpose()?.unwrap_or(0),
+        };
+        if parts.next().is_some() {
+            return Err(invalid());
+        }
+        Ok(version)
+    }
+
+    pub fn bump(self, part: BumpPart) -> Self {
+        match part {
+            BumpPart::Major => Version {
+                major: self.major + 1,
+                minor: 0,
+                patch: 0,
+            },
+            BumpPart::Minor => Version {
+                minor: self.minor + 1,
+                patch: 0,
+                ..self
+            },
+            BumpPart::Patch => Version {
+                patch: self.patch + 1,
+                ..self
+            },
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Rewrites version properties in `.csproj` files and version attributes in
+/// `AssemblyInfo.cs` files.
+fn update_assembly_versions(working_directory: &Path, depth: usize, version: &str) -> Result<()> {
+    let targets = find_files(working_directory, depth, "csproj", true)
+        .into_iter()
+        .map(|path| {
+            (
+                path,
+                &["<AssemblyVersion>", "<FileVersion>", "<Version>"][..],
+            )
+        })
+        .chain(
+            find_files(working_directory, depth, "cs", true)
+                .into_iter()
+                .filter(|path| {
+                    path.file_name()
+                        .is_some_and(|name| name == "AssemblyInfo.cs")
+                })
+                .map(|path| (path, &["AssemblyVersion(\"", "AssemblyFileVersion(\""][..])),
+        );
+
+    let mut updated = 0;
+    for (path, markers) in targets {
+        let mut content = fs::read_to_string(&path)?;
+        let mut count = 0;
+        for marker in markers {
+            let close = if marker.starts_with('<') { "</" } else { "\"" };
+            count += replace_values(&mut content, marker, close, version);
+        }
+        if count == 0 {
+            debug!("No version found in {}", path.display());
+            continue;
+        }
+        fs::write(&path, content)?;
+        info!(
+            "Updated assembly version in {}",
+            path.strip_prefix(working_directory)
+                .unwrap_or(&path)
+                .display()
+        );
+        updated += 1;
+    }
+
+    if updated == 0 {
+        warn!("No assembly version found in .csproj or AssemblyInfo.cs files");
+    }
+    Ok(())
+}
+
+/// Replaces the text between each `open` marker and the following `close`
+/// with `value`, returning how many were replaced.
+fn replace_values(content: &mut String, open: &str, close: &str, value: &str) -> usize {
+    let mut count = 0;
+    let mut from = 0;
+    while let Some(start) = content[from..].find(open).map(|i| from + i + open.len()) {
+        let Some(end) = content[start..].find(close).map(|i| start + i) else {
+            break;
+        };
+        content.replace_range(start..end, value);
+        from = start + value.len();
+        count += 1;
+    }
+    count
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_version_bump() {
+        let version = Version::parse("v1.4.2").unwrap();
+        assert_eq!(version.bump(BumpPart::Patch).to_string(), "1.4.3");
+        assert_eq!(version.bump(BumpPart::Minor).to_string(), "1.5.0");
+        assert_eq!(version.bump(BumpPart::Major).to_string(), "2.0.0");
+        assert_eq!(Version::parse("1.2").unwrap().to_string(), "1.2.0");
+        assert!(Version::parse("1.2.3.4").is_err());
+        assert!(Version::parse("1.x").is_err());
+    }
+
+    #[test]
+    fn test_update_assembly_versions() {
+        let root = tempfile::tempdir().unwrap();
+        let csproj = root.path().join("Source/MyMod/MyMod.csproj");
+        let info = root.path().join("Source/MyMod/Properties/AssemblyInfo.cs");
+        fs::create_dir_all(info.parent().unwrap()).unwrap();
+        fs::write(
+            &csproj,
+            "<Project><PropertyGroup><AssemblyVersion>1.0.0</AssemblyVersion><Version>1.0.0</Version></PropertyGroup></Project>",
+        )
+        .unwrap();
+        fs::write(
+            &info,
+            "[assembly: AssemblyVersion(\"1.0.0.0\")]\n[assembly: AssemblyFileVersion(\"1.0.0.0\")]\n",
+        )
+        .unwrap();
+
+        update_assembly_versions(root.path(), 4, "1.1.0").unwrap();
+        assert_eq!(
+            fs::read_to_string(&csproj).unwrap(),
+            "<Project><PropertyGroup><AssemblyVersion>1.1.0</AssemblyVersion><Version>1.1.0</Version></PropertyGroup></Project>"
+        );
+        assert_eq!(
+            fs::read_to_string(&info).unwrap(),
+            "[assembly: AssemblyVersion(\"1.1.0\")]\n[assembly: AssemblyFileVersion(\"1.1.0\")]\n"
+        );
+    }
+}
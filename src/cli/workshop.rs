@@ -0,0 +1,367 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{Result, anyhow};
+use clap::{Args, Subcommand};
+use log::{debug, info, warn};
+
+use super::{
+    CHANGENOTE_FILE_NAME, Config, ProjectConf,
+    check::validate_source,
+    package::file_name_safe,
+    publish::{collect_source, mirror, run_build, validate_about},
+};
+use crate::{
+    about::ModMetadata,
+    util::{RIMWORLD_APP_ID, get_dir},
+    vdf::{self, Object, Value},
+};
+
+pub const PUBLISHED_FILE_ID_PATH: &str = "About/PublishedFileId.txt";
+
+const PREVIEW_FILE_PATH: &str = "About/Preview.png";
+const STEAMCMD: &str = "steamcmd";
+const STAGING_DIR_NAME: &str = "workshop";
+
+#[derive(Debug, Args)]
+pub struct WorkshopArgs {
+    #[command(subcommand)]
+    pub command: WorkshopCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum WorkshopCommand {
+    /// Upload the mod to the Steam Workshop through steamcmd
+    Upload(WorkshopUploadArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct WorkshopUploadArgs {
+    /// Change note shown on the Workshop page, defaults to the one written by
+    /// 'rimpub changelog', which is removed after a successful upload
+    #[arg(short = 'm', long)]
+    pub changenote: Option<String>,
+
+    /// Steam account to log in with, defaults to 'steam_user' from the config
+    #[arg(long)]
+    pub user: Option<String>,
+
+    /// Only upload the folders the given game version loads, e.g. '1.5'
+    #[arg(long, value_name = "VERSION")]
+    pub game_version: Option<String>,
+
+    /// Skip the build step, e.g. for changes without code
+    #[arg(long)]
+    pub no_build: bool,
+
+    /// Skip validating About/About.xml and XML files before uploading
+    #[arg(long)]
+    pub no_validate: bool,
+
+    /// Stage the files and write the build item without running steamcmd
+    #[arg(short = 'n', long)]
+    pub dry_run: bool,
+}
+
+impl WorkshopArgs {
+    pub fn run(&self) -> Result<()> {
+        match &self.command {
+            WorkshopCommand::Upload(args) => args.run(),
+        }
+    }
+}
+
+impl WorkshopUploadArgs {
+    pub fn run(&self) -> Result<()> {
+        let config_global = Config::get_clone();
+        let working_directory = std::env::current_dir()?;
+        info!("Working directory: {}", working_directory.display());
+
+        let (mut config, _) = ProjectConf::load_current()?;
+        config.resolve_name();
+        info!("Working project: {}", config.name);
+
+        let build_report = if self.no_build {
+            info!("Skipping build step");
+            None
+        } else {
+            Some(run_build(&working_directory, &config.build)?)
+        };
+
+        if self.no_validate {
+            debug!("Skipping About.xml validation");
+        } else {
+            validate_about(&working_directory)?;
+        }
+
+        // steamcmd uploads a whole folder, so stage exactly what would be
+        // published
+        let staging = get_dir().join(STAGING_DIR_NAME);
+        let file_name = file_name_safe(&config.name);
+        let content_dir = staging.join(&file_name);
+        let (source, mut any_err) =
+            collect_source(&working_directory, &config, self.game_version.as_deref())?;
+        if !self.no_validate {
+            validate_source(&source)?;
+        }
+        any_err |= mirror(&source, &content_dir)?;
+        // A partly staged mod must never reach a public item
+        if any_err {
+            return Err(anyhow!(
+                "Failed to stage every file in {}, not uploading",
+                content_dir.display()
+            ));
+        }
+        info!(
+            "Staged {} file(s) in {}",
+            source.files.len(),
+            content_dir.display()
+        );
+
+        let published_id = read_published_id(&working_directory)?;
+        match &published_id {
+            Some(id) => info!("Updating Workshop item {}", id),
+            None => info!(
+                "No {} found, creating a new Workshop item",
+                PUBLISHED_FILE_ID_PATH
+            ),
+        }
+
+        let changenote_path = working_directory.join(CHANGENOTE_FILE_NAME);
+        let changenote_file = match &self.changenote {
+            Some(_) => None,
+            None => fs::read_to_string(&changenote_path)
+                .inspect(|_| info!("Using change note from {}", CHANGENOTE_FILE_NAME))
+                .ok(),
+        };
+        let changenote = self
+            .changenote
+            .clone()
+            .or_else(|| changenote_file.clone())
+            .unwrap_or_default();
+        let meta = ModMetadata::load(&working_directory).ok();
+        let preview = content_dir.join(PREVIEW_FILE_PATH);
+        if !preview.is_file() {
+            warn!(
+                "{} not found, uploading without a preview image",
+                PREVIEW_FILE_PATH
+            );
+        }
+        let item = build_item(
+            &content_dir,
+            preview.is_file().then_some(preview.as_path()),
+            published_id.as_deref(),
+            &changenote,
+            // Title and description are only set on creation, so edits made
+            // on the Workshop page are not overwritten
+            meta.as_ref().filter(|_| published_id.is_none()),
+        );
+        let vdf_path = staging.join(format!("{}.vdf", file_name));
+        fs::write(&vdf_path, item.to_vdf())?;
+        debug!("Workshop build item written to {}", vdf_path.display());
+
+        let steamcmd = config_global
+            .path_steamcmd
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(STEAMCMD));
+        let user = self
+            .user
+            .clone()
+            .or(config_global.steam_user)
+            .ok_or_else(|| {
+                anyhow!("No Steam account given, pass '--user' or run 'rimpub config set steam_user <name>'")
+            })?;
+
+        if self.dry_run {
+            info!(
+                "Would run: {} +login {} +workshop_build_item {} +quit",
+                steamcmd.display(),
+                user,
+                vdf_path.display()
+            );
+            return Ok(());
+        }
+
+        let uploaded_id = upload(&steamcmd, &user, &vdf_path)?;
+        match (published_id, uploaded_id) {
+            (None, Some(id)) => {
+                fs::write(working_directory.join(PUBLISHED_FILE_ID_PATH), &id)?;
+                info!(
+                    "Created Workshop item {}, saved to {}",
+                    id, PUBLISHED_FILE_ID_PATH
+                );
+            },
+            (None, None) => warn!(
+                "steamcmd did not report the new item id, add it to {} manually",
+                PUBLISHED_FILE_ID_PATH
+            ),
+            (Some(_), _) => {},
+        }
+        // Used up, so the next upload does not repeat this release's notes
+        if changenote_file.is_some() {
+            match fs::remove_file(&changenote_path) {
+                Ok(()) => debug!("Removed {}", CHANGENOTE_FILE_NAME),
+                Err(e) => warn!("Failed to remove {}: {}", CHANGENOTE_FILE_NAME, e),
+            }
+        }
+
+        if let Some(report) = build_report {
+            report.print_summary();
+        }
+
+        info!("Successfully uploaded {}", config.name);
+        Ok(())
+    }
+}
+
+fn read_published_id(working_directory: &Path) -> Result<Option<String>> {
+    let path = working_directory.join(PUBLISHED_FILE_ID_PATH);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let id = fs::read_to_string(&path)?.trim().to_string();
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_digit()) {
+        return Err(anyhow!(
+            "{} should only contain the numeric item id, found '{}'",
+            PUBLISHED_FILE_ID_PATH,
+            id
+        ));
+    }
+    Ok(Some(id))
+}
+
+/// The `workshopitem` VDF read by steamcmd's `workshop_build_item`, where a
+/// `publishedfileid` of 0 creates a new item.
+fn build_item(
+    content_dir: &Path,
+    preview: Option<&Path>,
+    published_id: Option<&str>,
+    changenote: &str,
+    meta: Option<&ModMetadata>,
+) -> Object {
+    let str = |s: &str| Value::Str(s.to_string());
+    let mut item = vec![
+        ("appid".to_string(), str(RIMWORLD_APP_ID)),
+        (
+            "contentfolder".to_string(),
+            str(&content_dir.to_string_lossy()),
+        ),
+    ];
+    if let Some(preview) = preview {
+        item.push(("previewfile".to_string(), str(&preview.to_string_lossy())));
+    }
+    item.push((
+        "publishedfileid".to_string(),
+        str(published_id.unwrap_or("0")),
+    ));
+    if let Some(meta) = meta {
+        item.push(("title".to_string(), str(&meta.name)));
+        item.push(("description".to_string(), str(&meta.description)));
+    }
+    item.push(("changenote".to_string(), str(changenote)));
+
+    Object(vec![("workshopitem".to_string(), Value::Obj(Object(item)))])
+}
+
+/// Runs steamcmd on the build item at `vdf_path`, returning the item id it
+/// writes back into the file.
+fn upload(steamcmd: &Path, user: &str, vdf_path: &Path) -> Result<Option<String>> {
+    info!(
+        "Executing {} for {}",
+        steamcmd.display(),
+        vdf_path.display()
+    );
+    // Inherit the terminal so password and Steam Guard prompts work
+    let status = Command::new(steamcmd)
+        .arg("+login")
+        .arg(user)
+        .arg("+workshop_build_item")
+        .arg(vdf_path)
+        .arg("+quit")
+        .status()
+        .map_err(|e| anyhow!("Failed to execute {}: {}", steamcmd.display(), e))?;
+    if !status.success() {
+        return Err(anyhow!("Workshop upload failed: {}", status));
+    }
+
+    let item = vdf::parse(&fs::read_to_string(vdf_path)?)?;
+    Ok(item
+        .get_path(&["workshopitem", "publishedfileid"])
+        .and_then(Value::as_str)
+        .filter(|id| *id != "0")
+        .map(String::from))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_build_item() {
+        let meta = ModMetadata {
+            name: "My Mod".into(),
+            description: "Does things".into(),
+            ..Default::default()
+        };
+        let item = build_item(
+            Path::new("/stage/My Mod"),
+            Some(Path::new("/stage/My Mod/About/Preview.png")),
+            None,
+            "Initial release",
+            Some(&meta),
+        );
+        let get = |key| {
+            item.get_path(&["workshopitem", key])
+                .and_then(Value::as_str)
+                .map(String::from)
+        };
+        assert_eq!(get("appid").as_deref(), Some("294100"));
+        assert_eq!(get("contentfolder").as_deref(), Some("/stage/My Mod"));
+        assert_eq!(get("publishedfileid").as_deref(), Some("0"));
+        assert_eq!(get("title").as_deref(), Some("My Mod"));
+
+        let item = build_item(Path::new("/stage"), None, Some("42"), "", None);
+        let workshop_item = item.get("workshopitem").and_then(Value::as_obj).unwrap();
+        assert!(workshop_item.get("previewfile").is_none());
+        assert!(workshop_item.get("title").is_none());
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn test_upload_with_stub() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = tempfile::tempdir().unwrap();
+        let stub = root.path().join("steamcmd");
+        let args_file = root.path().join("args.txt");
+        // Records its arguments and assigns an id like steamcmd does
+        fs::write(
+            &stub,
+            format!(
+                "#!/bin/sh\nprintf '%s\\n' \"$@\" > '{}'\nsed 's/\"0\"/\"123\"/' \"$4\" > \"$4.tmp\" && mv \"$4.tmp\" \"$4\"\n",
+                args_file.display()
+            ),
+        )
+        .unwrap();
+        fs::set_permissions(&stub, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let vdf_path = root.path().join("item.vdf");
+        let item = build_item(root.path(), None, None, "", None);
+        fs::write(&vdf_path, item.to_vdf()).unwrap();
+
+        assert_eq!(
+            upload(&stub, "bob", &vdf_path).unwrap().as_deref(),
+            Some("123")
+        );
+        assert_eq!(
+            fs::read_to_string(&args_file).unwrap(),
+            format!(
+                "+login\nbob\n+workshop_build_item\n{}\n+quit\n",
+                vdf_path.display()
+            )
+        );
+    }
+}
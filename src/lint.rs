@@ -0,0 +1,247 @@
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use roxmltree::{Document, Error, TextPos};
+
+pub use self::{
+    defs::{DefIndex, def_files},
+    patches::{DefsDocument, check_matches, check_patches, collect_patches},
+};
+
+mod defs;
+mod patches;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// A problem found in a mod file, positioned by 1-based line and column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    pub path: PathBuf,
+    pub line: u32,
+    pub column: u32,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Problem {
+    pub fn new(path: &Path, pos: TextPos, severity: Severity, message: impl Into<String>) -> Self {
+        Problem {
+            path: path.to_path_buf(),
+            line: pos.row,
+            column: pos.col,
+            severity,
+            message: message.into(),
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}: {}",
+            self.path.display(),
+            self.line,
+            self.column,
+            self.message
+        )
+    }
+}
+
+/// Checks every `.xml` file among `files`, given relative to `root`.
+pub fn check_xml_files(root: &Path, files: &[PathBuf]) -> Vec<Problem> {
+    files
+        .iter()
+        .filter(|path| is_xml(path))
+        .flat_map(|path| match fs::read(root.join(path)) {
+            Ok(bytes) => check_xml(path, &bytes),
+            Err(e) => vec![Problem::new(
+                path,
+                TextPos::new(1, 1),
+                Severity::Error,
+                format!("Failed to read: {}", e),
+            )],
+        })
+        .collect()
+}
+
+fn is_xml(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("xml"))
+}
+
+/// Checks that `bytes` is a well-formed UTF-8 XML document, the only kind
+/// RimWorld loads reliably.
+pub fn check_xml(path: &Path, bytes: &[u8]) -> Vec<Problem> {
+    let problem = |pos, severity, message: String| vec![Problem::new(path, pos, severity, message)];
+
+    if bytes.starts_with(&[0xFF, 0xFE]) || bytes.starts_with(&[0xFE, 0xFF]) {
+        return problem(
+            TextPos::new(1, 1),
+            Severity::Error,
+            "File is UTF-16 encoded, save it as UTF-8".to_string(),
+        );
+    }
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    let text = match std::str::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(e) => {
+            // Valid up to the error, so the prefix can be measured as text
+            let valid = std::str::from_utf8(&bytes[..e.valid_up_to()]).unwrap_or_default();
+            return problem(
+                text_pos_at(valid, valid.len()),
+                Severity::Error,
+                "Invalid UTF-8 byte sequence, save the file as UTF-8".to_string(),
+            );
+        },
+    };
+
+    if let Err(e) = Document::parse(text) {
+        return problem(error_pos(&e, text), Severity::Error, describe(&e));
+    }
+
+    let mut problems = Vec::new();
+    if let Some(encoding) = declared_encoding(text)
+        && !encoding.eq_ignore_ascii_case("utf-8")
+        && !encoding.eq_ignore_ascii_case("utf8")
+    {
+        problems.push(Problem::new(
+            path,
+            TextPos::new(1, 1),
+            Severity::Warning,
+            format!("Declared encoding '{}' is not UTF-8", encoding),
+        ));
+    }
+    if text.contains('\u{FEFF}') {
+        let offset = text.find('\u{FEFF}').unwrap_or_default();
+        problems.push(Problem::new(
+            path,
+            text_pos_at(text, offset),
+            Severity::Warning,
+            "Stray byte order mark inside the file",
+        ));
+    }
+    problems
+}
+
+/// Value of the `encoding` attribute in the XML declaration, if any.
+fn declared_encoding(text: &str) -> Option<&str> {
+    let declaration = &text[..text.strip_prefix("<?xml")?.find("?>")? + "<?xml".len()];
+    let rest = &declaration[declaration.find("encoding")? + "encoding".len()..];
+    let rest = rest.trim_start().strip_prefix('=')?.trim_start();
+    let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let rest = &rest[1..];
+    Some(&rest[..rest.find(quote)?])
+}
+
+/// Position of `e`, moved to the end of the text when the parser only knows
+/// the input ended early.
+fn error_pos(e: &Error, text: &str) -> TextPos {
+    match e {
+        Error::UnclosedRootNode | Error::UnexpectedEndOfStream => text_pos_at(text, text.len()),
+        e => e.pos(),
+    }
+}
+
+fn describe(e: &Error) -> String {
+    match e {
+        Error::UnexpectedCloseTag(expected, actual, _) => {
+            format!("Found '</{}>' while '<{}>' is not closed", actual, expected)
+        },
+        Error::UnclosedRootNode | Error::UnexpectedEndOfStream => {
+            "Unexpected end of file, an element is not closed".to_string()
+        },
+        Error::UnknownEntityReference(name, _) => format!(
+            "Unknown entity '&{};', write a literal '&' as '&amp;'",
+            name
+        ),
+        Error::MalformedEntityReference(_) => {
+            "Malformed entity reference, write a literal '&' as '&amp;'".to_string()
+        },
+        Error::InvalidAttributeValue(_) => {
+            "Unescaped '<' in attribute value, write it as '&lt;'".to_string()
+        },
+        Error::DuplicatedAttribute(name, _) => format!("Duplicate attribute '{}'", name),
+        Error::NoRootNode => "No root element".to_string(),
+        Error::UnexpectedDeclaration(_) => {
+            "XML declaration must be at the very start of the file".to_string()
+        },
+        Error::NonXmlChar(c, _) => format!("Character {:?} is not allowed in XML", c),
+        e => {
+            // The message ends with the position, which is reported separately
+            let message = e.to_string();
+            let suffix = format!(" at {}", e.pos());
+            let message = message.strip_suffix(&suffix).unwrap_or(&message);
+            let mut chars = message.chars();
+            chars
+                .next()
+                .map(|c| c.to_uppercase().chain(chars).collect())
+                .unwrap_or_default()
+        },
+    }
+}
+
+/// 1-based line and column of the byte `offset` in `text`, counting columns
+/// in characters.
+fn text_pos_at(text: &str, offset: usize) -> TextPos {
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    TextPos::new(
+        before.matches('\n').count() as u32 + 1,
+        before[line_start..].chars().count() as u32 + 1,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn check(bytes: &[u8]) -> Vec<(u32, u32, Severity, String)> {
+        check_xml(Path::new("Defs/Things.xml"), bytes)
+            .into_iter()
+            .map(|p| (p.line, p.column, p.severity, p.message))
+            .collect()
+    }
+
+    #[test]
+    fn test_check_xml() {
+        let valid = "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<Defs>\n\t<ThingDef/>\n</Defs>\n";
+        assert!(check(valid.as_bytes()).is_empty());
+        assert!(check(format!("\u{FEFF}{}", valid).as_bytes()).is_empty());
+
+        let unclosed = check(b"<Defs>\n\t<ThingDef>\n\t\t<label>x</label>\n</Defs>\n");
+        assert_eq!(unclosed.len(), 1);
+        assert_eq!((unclosed[0].0, unclosed[0].1), (4, 1));
+        assert!(unclosed[0].3.contains("'<ThingDef>' is not closed"));
+
+        let truncated = check(b"<Defs>\n\t<ThingDef>\n");
+        assert_eq!((truncated[0].0, truncated[0].1), (3, 1));
+
+        let entity = check(b"<Defs>\n\t<label>Salt & Pepper</label>\n</Defs>");
+        assert_eq!(entity[0].0, 2);
+        assert!(entity[0].3.contains("&amp;"));
+
+        let utf16 = check(&[0xFF, 0xFE, b'<', 0]);
+        assert!(utf16[0].3.contains("UTF-16"));
+
+        let latin1 = check(b"<Defs>\n\t<label>caf\xE9</label>\n</Defs>");
+        assert_eq!(
+            (latin1[0].0, latin1[0].1, latin1[0].2),
+            (2, 12, Severity::Error)
+        );
+
+        let declared = check(b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><Defs/>");
+        assert_eq!(declared[0].2, Severity::Warning);
+        assert!(declared[0].3.contains("ISO-8859-1"));
+    }
+}
@@ -0,0 +1,467 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use ignore::WalkBuilder;
+use roxmltree::{Document, Node, TextPos};
+
+use super::{Problem, Severity, is_xml};
+use crate::load_folders::LoadScopes;
+
+const DEFS_TAG: &str = "Defs";
+
+/// Fields holding references to other defs, as the field name, how the
+/// names are written and the referenced def type.
+const REFERENCE_FIELDS: &[(&str, RefKind, &str)] = &[
+    ("thingDef", RefKind::Text, "ThingDef"),
+    ("race", RefKind::Text, "ThingDef"),
+    ("researchPrerequisite", RefKind::Text, "ResearchProjectDef"),
+    (
+        "designationCategory",
+        RefKind::Text,
+        "DesignationCategoryDef",
+    ),
+    ("workType", RefKind::Text, "WorkTypeDef"),
+    ("hediff", RefKind::Text, "HediffDef"),
+    ("jobDef", RefKind::Text, "JobDef"),
+    ("skill", RefKind::Text, "SkillDef"),
+    ("thingDefs", RefKind::List, "ThingDef"),
+    ("recipeUsers", RefKind::List, "ThingDef"),
+    ("stuffCategories", RefKind::List, "StuffCategoryDef"),
+    ("thingCategories", RefKind::List, "ThingCategoryDef"),
+    ("categories", RefKind::List, "ThingCategoryDef"),
+    ("researchPrerequisites", RefKind::List, "ResearchProjectDef"),
+    ("prerequisites", RefKind::List, "ResearchProjectDef"),
+    ("hiddenPrerequisites", RefKind::List, "ResearchProjectDef"),
+    ("recipes", RefKind::List, "RecipeDef"),
+    ("costList", RefKind::Keys, "ThingDef"),
+    ("products", RefKind::Keys, "ThingDef"),
+    ("statBases", RefKind::Keys, "StatDef"),
+    ("statOffsets", RefKind::Keys, "StatDef"),
+    ("statFactors", RefKind::Keys, "StatDef"),
+    ("equippedStatOffsets", RefKind::Keys, "StatDef"),
+];
+
+/// Prefixes of thing defs the game generates at load time instead of reading
+/// them from XML.
+const IMPLIED_THING_PREFIXES: &[&str] = &[
+    "Blueprint_",
+    "Frame_",
+    "Corpse_",
+    "Meat_",
+    "Minified",
+    "Techprint_",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RefKind {
+    /// `<thingDef>Steel</thingDef>`
+    Text,
+    /// `<thingDefs><li>Steel</li></thingDefs>`
+    List,
+    /// `<costList><Steel>10</Steel></costList>`
+    Keys,
+}
+
+/// A def entry under `<Defs>`, positioned where it starts.
+#[derive(Debug, Clone)]
+pub struct Def {
+    pub def_type: String,
+    pub def_name: Option<String>,
+    pub name: Option<String>,
+    pub parent_name: Option<String>,
+    pub is_abstract: bool,
+    /// Whether the def is only loaded with other mods, through `MayRequire`
+    pub optional: bool,
+    pub path: PathBuf,
+    pub pos: TextPos,
+}
+
+/// A def name used by a field expecting a def of `def_type`.
+#[derive(Debug, Clone)]
+pub struct Reference {
+    pub def_type: &'static str,
+    pub name: String,
+    pub path: PathBuf,
+    pub pos: TextPos,
+}
+
+/// Defs and references found in a set of XML files.
+#[derive(Debug, Default)]
+pub struct DefIndex {
+    pub defs: Vec<Def>,
+    pub references: Vec<Reference>,
+}
+
+impl DefIndex {
+    /// Indexes the `.xml` files among `files`, given relative to `root`.
+    pub fn collect(root: &Path, files: &[PathBuf]) -> Self {
+        let mut index = DefIndex::default();
+        for path in files.iter().filter(|path| is_xml(path)) {
+            if let Ok(text) = fs::read_to_string(root.join(path)) {
+                index.add_file(path, &text);
+            }
+        }
+        index
+    }
+
+    /// Indexes the XML files in every `Defs` folder of the mod at `mod_root`,
+    /// such as a dependency or the game's `Data/Core`.
+    pub fn load_mod(mod_root: &Path) -> Self {
+        let files = def_files(mod_root);
+        Self::collect(mod_root, &files)
+    }
+
+    pub fn extend(&mut self, other: DefIndex) {
+        self.defs.extend(other.defs);
+        self.references.extend(other.references);
+    }
+
+    /// Indexes a single file, skipping anything that is not a `<Defs>`
+    /// document. Malformed XML is left to the well-formedness check.
+    pub fn add_file(&mut self, path: &Path, text: &str) {
+        let text = text.strip_prefix('\u{FEFF}').unwrap_or(text);
+        let Ok(doc) = Document::parse(text) else {
+            return;
+        };
+        let root = doc.root_element();
+        if root.tag_name().name() != DEFS_TAG {
+            return;
+        }
+
+        let pos = |node: Node| doc.text_pos_at(node.range().start);
+        for node in root.children().filter(Node::is_element) {
+            self.defs.push(Def {
+                def_type: node.tag_name().name().to_string(),
+                def_name: crate::xml::child_text(node, "defName").filter(|s| !s.is_empty()),
+                name: node.attribute("Name").map(String::from),
+                parent_name: node.attribute("ParentName").map(String::from),
+                is_abstract: node
+                    .attribute("Abstract")
+                    .is_some_and(|v| v.eq_ignore_ascii_case("true")),
+                optional: may_require(node),
+                path: path.to_path_buf(),
+                pos: pos(node),
+            });
+
+            for field in node.descendants().filter(Node::is_element) {
+                let Some(&(_, kind, def_type)) = REFERENCE_FIELDS
+                    .iter()
+                    .find(|(tag, ..)| *tag == field.tag_name().name())
+                else {
+                    continue;
+                };
+                let names = match kind {
+                    RefKind::Text => vec![(field, leaf_text(field))],
+                    RefKind::List => elements(field)
+                        .filter(|n| n.tag_name().name() == "li")
+                        .map(|n| (n, leaf_text(n)))
+                        .collect(),
+                    RefKind::Keys => elements(field)
+                        .filter(|n| n.tag_name().name() != "li")
+                        .map(|n| (n, Some(n.tag_name().name())))
+                        .collect(),
+                };
+                for (field_node, name) in names {
+                    // Optional content only loaded with other mods active
+                    if field_node
+                        .ancestors()
+                        .take_while(|n| *n != root)
+                        .any(may_require)
+                    {
+                        continue;
+                    }
+                    if let Some(name) = name.filter(|name| !name.is_empty()) {
+                        self.references.push(Reference {
+                            def_type,
+                            name: name.to_string(),
+                            path: path.to_path_buf(),
+                            pos: pos(field_node),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    /// Reports duplicate defNames and Names among files `scopes` loads
+    /// together, and when the defs of the game and dependencies are known,
+    /// parents and references that resolve to nothing.
+    pub fn check(&self, scopes: &LoadScopes, external: Option<&DefIndex>) -> Vec<Problem> {
+        let conflicts = |a: &Def, b: &Def| scopes.loaded_together(&a.path, &b.path);
+        let mut problems = Vec::new();
+
+        let mut seen_defs: HashMap<(&str, &str), Vec<&Def>> = HashMap::new();
+        let mut seen_names: HashMap<&str, Vec<&Def>> = HashMap::new();
+        for def in &self.defs {
+            if let Some(def_name) = &def.def_name
+                && !def.is_abstract
+            {
+                let seen = seen_defs.entry((&def.def_type, def_name)).or_default();
+                if let Some(other) = seen.iter().find(|other| conflicts(other, def)) {
+                    problems.push(def_problem(
+                        def,
+                        Severity::Error,
+                        format!(
+                            "Duplicate {} '{}', also defined at {}",
+                            def.def_type,
+                            def_name,
+                            location(other)
+                        ),
+                    ));
+                }
+                seen.push(def);
+            }
+            if let Some(name) = &def.name {
+                let seen = seen_names.entry(name).or_default();
+                if let Some(other) = seen.iter().find(|other| conflicts(other, def)) {
+                    problems.push(def_problem(
+                        def,
+                        Severity::Error,
+                        format!(
+                            "Duplicate Name '{}', also used at {}",
+                            name,
+                            location(other)
+                        ),
+                    ));
+                }
+                seen.push(def);
+            }
+        }
+
+        // Without the game's own defs most parents and references look
+        // missing, so only duplicates are reliable
+        let Some(external) = external else {
+            return problems;
+        };
+
+        let external_names = external
+            .defs
+            .iter()
+            .filter_map(|def| def.name.as_deref())
+            .collect::<HashSet<_>>();
+        for def in self.defs.iter().filter(|def| !def.optional) {
+            let Some(parent) = &def.parent_name else {
+                continue;
+            };
+            let found = external_names.contains(parent.as_str())
+                || seen_names.get(parent.as_str()).is_some_and(|defs| {
+                    defs.iter()
+                        .any(|other| scopes.loaded_with(&def.path, &other.path))
+                });
+            // A warning, as dependencies that cannot be found look the same
+            if !found {
+                problems.push(def_problem(
+                    def,
+                    Severity::Warning,
+                    format!(
+                        "ParentName '{}' does not match any Name in the mod, the game or its dependencies",
+                        parent
+                    ),
+                ));
+            }
+        }
+
+        let mut defined: HashMap<&str, HashSet<&str>> = HashMap::new();
+        for def in external.defs.iter().chain(&self.defs) {
+            if let Some(def_name) = &def.def_name {
+                defined.entry(&def.def_type).or_default().insert(def_name);
+            }
+        }
+        // Def classes from mods, like `AlienRace.ThingDef_AlienRace`, may
+        // subclass any of the base types
+        let subclassed = defined
+            .iter()
+            .filter(|(def_type, _)| def_type.contains(['.', '_']))
+            .flat_map(|(_, names)| names.iter().copied())
+            .collect::<HashSet<_>>();
+        for reference in &self.references {
+            let name = reference.name.as_str();
+            let found = defined
+                .get(reference.def_type)
+                .is_some_and(|names| names.contains(name))
+                || subclassed.contains(name)
+                || (reference.def_type == "ThingDef"
+                    && IMPLIED_THING_PREFIXES
+                        .iter()
+                        .any(|prefix| name.starts_with(prefix)));
+            if !found {
+                problems.push(Problem::new(
+                    &reference.path,
+                    reference.pos,
+                    Severity::Warning,
+                    format!("Reference to undefined {} '{}'", reference.def_type, name),
+                ));
+            }
+        }
+
+        problems
+    }
+}
+
+/// The `.xml` files in every `Defs` folder of the mod at `mod_root`, relative
+/// to it.
+pub fn def_files(mod_root: &Path) -> Vec<PathBuf> {
+    WalkBuilder::new(mod_root)
+        .standard_filters(false)
+        .build()
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let relative = entry.path().strip_prefix(mod_root).ok()?;
+            (is_xml(relative) && relative.components().any(|c| c.as_os_str() == DEFS_TAG))
+                .then(|| relative.to_path_buf())
+        })
+        .collect()
+}
+
+fn elements<'a, 'input>(node: Node<'a, 'input>) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children().filter(Node::is_element)
+}
+
+/// Trimmed text of an element without child elements.
+fn leaf_text<'a>(node: Node<'a, '_>) -> Option<&'a str> {
+    if elements(node).next().is_some() {
+        return None;
+    }
+    node.text().map(str::trim)
+}
+
+fn may_require(node: Node) -> bool {
+    node.attribute("MayRequire").is_some() || node.attribute("MayRequireAnyOf").is_some()
+}
+
+fn location(def: &Def) -> String {
+    format!("{}:{}", def.path.display(), def.pos.row)
+}
+
+fn def_problem(def: &Def, severity: Severity, message: String) -> Problem {
+    Problem::new(&def.path, def.pos, severity, message)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn index(files: &[(&str, &str)]) -> DefIndex {
+        let mut index = DefIndex::default();
+        for (path, text) in files {
+            index.add_file(Path::new(path), text);
+        }
+        index
+    }
+
+    fn messages(problems: &[Problem]) -> Vec<String> {
+        problems.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_duplicate_defs() {
+        let root = tempfile::tempdir().unwrap();
+        for dir in ["1.5", "1.6"] {
+            fs::create_dir(root.path().join(dir)).unwrap();
+        }
+        let scopes = LoadScopes::new(root.path()).unwrap();
+        let thing = "<Defs>\n\t<ThingDef><defName>Gun</defName></ThingDef>\n</Defs>";
+        let mod_index = index(&[
+            ("Defs/A.xml", thing),
+            ("Defs/B.xml", thing),
+            ("1.5/Defs/C.xml", thing),
+            (
+                "1.6/Defs/C.xml",
+                "<Defs><HediffDef><defName>Gun</defName></HediffDef></Defs>",
+            ),
+            ("Patches/P.xml", "<Patch/>"),
+        ]);
+        assert_eq!(mod_index.defs.len(), 4);
+        assert_eq!(
+            messages(&mod_index.check(&scopes, None)),
+            [
+                "Defs/B.xml:2:2: Duplicate ThingDef 'Gun', also defined at Defs/A.xml:2",
+                "1.5/Defs/C.xml:2:2: Duplicate ThingDef 'Gun', also defined at Defs/A.xml:2",
+            ]
+        );
+
+        // The same def in two version folders is never loaded twice
+        let versioned = index(&[("1.5/Defs/A.xml", thing), ("1.6/Defs/A.xml", thing)]);
+        assert!(versioned.check(&scopes, None).is_empty());
+        assert_eq!(versioned.check(&LoadScopes::default(), None).len(), 1);
+    }
+
+    #[test]
+    fn test_parents_and_references() {
+        let core = index(&[(
+            "Defs/Core.xml",
+            "<Defs>
+                <ThingDef Name=\"BuildingBase\" Abstract=\"True\"/>
+                <ThingDef><defName>Steel</defName></ThingDef>
+                <StatDef><defName>MaxHitPoints</defName></StatDef>
+            </Defs>",
+        )]);
+        let mod_index = index(&[(
+            "Defs/Things.xml",
+            "<Defs>
+                <ThingDef ParentName=\"BuildingBase\">
+                    <defName>Wall</defName>
+                    <statBases><MaxHitPoints>100</MaxHitPoints><Beauty>1</Beauty></statBases>
+                    <costList><Steel>5</Steel><Plasteel MayRequire=\"Ludeon.RimWorld.Royalty\">1</Plasteel></costList>
+                    <recipeUsers><li>Wall</li><li>Blueprint_Wall</li><li>Bench</li></recipeUsers>
+                </ThingDef>
+                <ThingDef ParentName=\"MissingBase\"><defName>Door</defName></ThingDef>
+                <ThingDef ParentName=\"MechBase\" MayRequire=\"Ludeon.RimWorld.Biotech\">
+                    <defName>Mech</defName>
+                    <costList><Plasteel>5</Plasteel></costList>
+                </ThingDef>
+            </Defs>",
+        )]);
+
+        let scopes = LoadScopes::default();
+        assert!(mod_index.check(&scopes, None).is_empty());
+        let problems = mod_index.check(&scopes, Some(&core));
+        assert_eq!(
+            problems
+                .iter()
+                .map(|p| (p.line, p.severity, p.message.as_str()))
+                .collect::<Vec<_>>(),
+            [
+                (
+                    8,
+                    Severity::Warning,
+                    "ParentName 'MissingBase' does not match any Name in the mod, the game or its dependencies"
+                ),
+                (
+                    4,
+                    Severity::Warning,
+                    "Reference to undefined StatDef 'Beauty'"
+                ),
+                (
+                    6,
+                    Severity::Warning,
+                    "Reference to undefined ThingDef 'Bench'"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_load_mod() {
+        let root = tempfile::tempdir().unwrap();
+        let defs = root.path().join("1.5/Defs/Sub");
+        fs::create_dir_all(&defs).unwrap();
+        fs::write(
+            defs.join("A.xml"),
+            "<Defs><ThingDef><defName>Gun</defName></ThingDef></Defs>",
+        )
+        .unwrap();
+        fs::write(
+            root.path().join("1.5/Other.xml"),
+            "<Defs><ThingDef><defName>Knife</defName></ThingDef></Defs>",
+        )
+        .unwrap();
+
+        let index = DefIndex::load_mod(root.path());
+        assert_eq!(index.defs.len(), 1);
+        assert_eq!(index.defs[0].def_name.as_deref(), Some("Gun"));
+    }
+}
@@ -0,0 +1,329 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use roxmltree::{Document, Node, TextPos};
+use sxd_document::{Package, dom};
+use sxd_xpath::{Context, Factory, Value};
+
+use super::{Problem, Severity, is_xml};
+
+const PATCH_TAG: &str = "Patch";
+const DEFS_TAG: &str = "Defs";
+
+/// Operations whose `xpath` is a test, where matching nothing is a result
+/// rather than a failure.
+const TEST_OPERATIONS: &[&str] = &["PatchOperationConditional", "PatchOperationTest"];
+
+/// An operation with an `xpath` in a `<Patch>` file, positioned at its
+/// `<xpath>` element.
+#[derive(Debug, Clone)]
+pub struct PatchOperation {
+    pub class: String,
+    pub xpath: String,
+    pub path: PathBuf,
+    pub pos: TextPos,
+    /// Whether matching nothing is expected, like for tests, operations that
+    /// only run with another mod, or ones marked `<success>Always</success>`
+    pub optional: bool,
+}
+
+/// Finds the patch operations in the `.xml` files among `files`, given
+/// relative to `root`.
+pub fn collect_patches(root: &Path, files: &[PathBuf]) -> Vec<PatchOperation> {
+    let mut operations = Vec::new();
+    for path in files.iter().filter(|path| is_xml(path)) {
+        if let Ok(text) = fs::read_to_string(root.join(path)) {
+            add_patch_file(&mut operations, path, &text);
+        }
+    }
+    operations
+}
+
+fn add_patch_file(operations: &mut Vec<PatchOperation>, path: &Path, text: &str) {
+    let text = text.strip_prefix('\u{FEFF}').unwrap_or(text);
+    let Ok(doc) = Document::parse(text) else {
+        return;
+    };
+    if doc.root_element().tag_name().name() != PATCH_TAG {
+        return;
+    }
+
+    // Operations nest through `operations`, `match` and `nomatch`, so any
+    // element with an `xpath` child is one
+    for xpath in doc
+        .descendants()
+        .filter(|n| n.is_element() && n.tag_name().name() == "xpath")
+    {
+        let Some(operation) = xpath.parent_element() else {
+            continue;
+        };
+        let class = operation.attribute("Class").unwrap_or_default();
+        operations.push(PatchOperation {
+            class: class.to_string(),
+            xpath: xpath.text().unwrap_or_default().trim().to_string(),
+            path: path.to_path_buf(),
+            pos: doc.text_pos_at(xpath.range().start),
+            optional: TEST_OPERATIONS.contains(&class)
+                || operation.ancestors().any(is_optional_operation),
+        });
+    }
+}
+
+fn is_optional_operation(node: Node) -> bool {
+    node.is_element()
+        && (node.attribute("Class") == Some("PatchOperationFindMod")
+            || node.attribute("MayRequire").is_some()
+            || node.attribute("MayRequireAnyOf").is_some()
+            || crate::xml::child_text(node, "success").as_deref() == Some("Always"))
+}
+
+/// Reports xpaths that do not parse, and ones that parse but are not
+/// anchored at the document root.
+pub fn check_patches(operations: &[PatchOperation]) -> Vec<Problem> {
+    let factory = Factory::new();
+    let mut problems = Vec::new();
+    for operation in operations {
+        let problem =
+            |severity, message| Problem::new(&operation.path, operation.pos, severity, message);
+        let xpath = operation.xpath.as_str();
+        match factory.build(xpath) {
+            Ok(Some(_)) => {},
+            Ok(None) => {
+                problems.push(problem(Severity::Error, "Empty xpath".to_string()));
+                continue;
+            },
+            Err(e) => {
+                problems.push(problem(
+                    Severity::Error,
+                    format!("Invalid xpath '{}': {}", xpath, words(&e.to_string())),
+                ));
+                continue;
+            },
+        }
+
+        if xpath.starts_with("Defs/") {
+            problems.push(problem(
+                Severity::Warning,
+                format!("xpath '{}' should start with '/Defs/'", xpath),
+            ));
+        } else if !xpath.starts_with('/') {
+            problems.push(problem(
+                Severity::Warning,
+                format!(
+                    "xpath '{}' does not start with '/', anchor it like '/Defs/...'",
+                    xpath
+                ),
+            ));
+        }
+    }
+    problems
+}
+
+/// Spells out the `CamelCase` error kinds the xpath parser displays, like
+/// `EmptyPredicate` as `empty predicate`.
+fn words(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 8);
+    let mut prev_lower = false;
+    for c in text.chars() {
+        if c.is_uppercase() {
+            if prev_lower {
+                out.push(' ');
+            }
+            out.extend(c.to_lowercase());
+        } else {
+            out.push(c);
+        }
+        prev_lower = c.is_lowercase();
+    }
+    out
+}
+
+/// The combined `<Defs>` document the game applies patches to.
+pub struct DefsDocument {
+    package: Package,
+}
+
+impl Default for DefsDocument {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DefsDocument {
+    pub fn new() -> Self {
+        let package = Package::new();
+        {
+            let doc = package.as_document();
+            let defs = doc.create_element(DEFS_TAG);
+            doc.root().append_child(defs);
+        }
+        DefsDocument { package }
+    }
+
+    /// Adds the defs in `text`, skipping anything that is not a `<Defs>`
+    /// document.
+    pub fn add_file(&mut self, text: &str) {
+        let text = text.strip_prefix('\u{FEFF}').unwrap_or(text);
+        let Ok(source) = Document::parse(text) else {
+            return;
+        };
+        if source.root_element().tag_name().name() != DEFS_TAG {
+            return;
+        }
+        let doc = self.package.as_document();
+        let Some(defs) = doc.root().children().into_iter().find_map(|n| n.element()) else {
+            return;
+        };
+        for node in source.root_element().children().filter(Node::is_element) {
+            defs.append_child(copy_element(&doc, node));
+        }
+    }
+
+    /// How many nodes `xpath` selects, or `None` when it cannot be evaluated
+    /// or does not select nodes.
+    pub fn count_matches(&self, xpath: &str) -> Option<usize> {
+        let xpath = Factory::new().build(xpath).ok()??;
+        let doc = self.package.as_document();
+        match xpath.evaluate(&Context::new(), doc.root()).ok()? {
+            Value::Nodeset(nodes) => Some(nodes.size()),
+            _ => None,
+        }
+    }
+}
+
+/// Copies `node` into `doc`, leaving out whitespace-only text like the game
+/// does.
+fn copy_element<'d>(doc: &dom::Document<'d>, node: Node) -> dom::Element<'d> {
+    let element = doc.create_element(node.tag_name().name());
+    for attribute in node.attributes() {
+        element.set_attribute_value(attribute.name(), attribute.value());
+    }
+    for child in node.children() {
+        if child.is_element() {
+            element.append_child(copy_element(doc, child));
+        } else if let Some(text) = child.text().filter(|_| child.is_text())
+            && !text.trim().is_empty()
+        {
+            element.append_child(doc.create_text(text));
+        }
+    }
+    element
+}
+
+/// Reports required operations whose xpath selects nothing in `defs`.
+pub fn check_matches(operations: &[PatchOperation], defs: &DefsDocument) -> Vec<Problem> {
+    operations
+        .iter()
+        .filter(|operation| !operation.optional)
+        .filter(|operation| defs.count_matches(&operation.xpath) == Some(0))
+        .map(|operation| {
+            Problem::new(
+                &operation.path,
+                operation.pos,
+                Severity::Warning,
+                format!(
+                    "{} xpath '{}' matches nothing in Core, the mod or its dependencies",
+                    if operation.class.is_empty() {
+                        "Operation"
+                    } else {
+                        &operation.class
+                    },
+                    operation.xpath
+                ),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const PATCH: &str = r#"<Patch>
+    <Operation Class="PatchOperationReplace">
+        <xpath>/Defs/ThingDef[defName="Wall"]/label</xpath>
+        <value><label>wall</label></value>
+    </Operation>
+    <Operation Class="PatchOperationAdd">
+        <xpath>Defs/ThingDef[defName="Door"]</xpath>
+        <value><tag/></value>
+    </Operation>
+    <Operation Class="PatchOperationSequence">
+        <operations>
+            <li Class="PatchOperationRemove">
+                <xpath>*/ThingDef[defName="Wall"]/description</xpath>
+            </li>
+            <li Class="PatchOperationRemove">
+                <xpath>/Defs/ThingDef[defName=</xpath>
+            </li>
+        </operations>
+    </Operation>
+    <Operation Class="PatchOperationFindMod">
+        <mods><li>Other Mod</li></mods>
+        <match Class="PatchOperationConditional">
+            <xpath>/Defs/OtherDef</xpath>
+        </match>
+    </Operation>
+</Patch>"#;
+
+    fn operations() -> Vec<PatchOperation> {
+        let mut operations = Vec::new();
+        add_patch_file(&mut operations, Path::new("Patches/P.xml"), PATCH);
+        add_patch_file(&mut operations, Path::new("Defs/D.xml"), "<Defs/>");
+        operations
+    }
+
+    #[test]
+    fn test_check_patches() {
+        let operations = operations();
+        assert_eq!(operations.len(), 5);
+        assert_eq!(operations[2].class, "PatchOperationRemove");
+        assert!(operations[4].optional);
+
+        let problems = check_patches(&operations)
+            .into_iter()
+            .map(|p| (p.line, p.severity))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            problems,
+            [
+                (7, Severity::Warning),
+                (13, Severity::Warning),
+                (16, Severity::Error),
+            ]
+        );
+        assert_eq!(words("EmptyPredicate"), "empty predicate");
+    }
+
+    #[test]
+    fn test_check_matches() {
+        let mut defs = DefsDocument::new();
+        defs.add_file(
+            "<Defs>\n\t<ThingDef>\n\t\t<defName>Wall</defName>\n\t\t<label>Wall</label>\n\t</ThingDef>\n</Defs>",
+        );
+        defs.add_file("<Patch/>");
+        assert_eq!(defs.count_matches("/Defs/ThingDef"), Some(1));
+        assert_eq!(defs.count_matches("Defs/ThingDef/defName"), Some(1));
+        assert_eq!(defs.count_matches("count(/Defs/ThingDef)"), None);
+
+        let problems = check_matches(&operations(), &defs)
+            .into_iter()
+            .map(|p| (p.line, p.message))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            problems,
+            [
+                (
+                    7,
+                    "PatchOperationAdd xpath 'Defs/ThingDef[defName=\"Door\"]' matches nothing in Core, the mod or its dependencies".to_string()
+                ),
+                (
+                    13,
+                    "PatchOperationRemove xpath '*/ThingDef[defName=\"Wall\"]/description' matches nothing in Core, the mod or its dependencies".to_string()
+                ),
+            ]
+        );
+    }
+}
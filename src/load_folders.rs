@@ -0,0 +1,497 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Result, anyhow, bail};
+use log::{debug, warn};
+use roxmltree::Document;
+
+use crate::{cli::LoadFoldersConf, xml::XmlWriter};
+
+pub const LOAD_FOLDERS_FILE_NAME: &str = "LoadFolders.xml";
+
+/// Folders loaded when no `shared` folders are configured, if they exist.
+const DEFAULT_SHARED_FOLDERS: &[&str] = &["Common"];
+
+const ROOT_TAG: &str = "loadFolders";
+
+/// Typed view of `LoadFolders.xml`, mapping each game version to the folders
+/// RimWorld loads for it, in load order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LoadFolders {
+    pub versions: Vec<(String, Vec<LoadFolder>)>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LoadFolder {
+    pub path: String,
+    pub if_mod_active: Option<String>,
+    pub if_mod_not_active: Option<String>,
+}
+
+impl LoadFolder {
+    fn plain(path: &str) -> Self {
+        LoadFolder {
+            path: path.to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+impl LoadFolders {
+    /// Builds the folder list for every version directory under `mod_root`,
+    /// falling back to `fallback_versions` when there are none.
+    pub fn generate(
+        mod_root: &Path,
+        conf: &LoadFoldersConf,
+        fallback_versions: &[String],
+    ) -> Result<Self> {
+        let mut versions = find_version_dirs(mod_root)?;
+        let has_version_dirs = !versions.is_empty();
+        if !has_version_dirs {
+            debug!("No version directories found, using supported versions");
+            versions = fallback_versions.to_vec();
+            versions.sort_by_key(|v| parse_version(v));
+        }
+        if versions.is_empty() {
+            bail!("No version directories or supported versions to generate load folders for");
+        }
+
+        let shared = if conf.shared.is_empty() {
+            DEFAULT_SHARED_FOLDERS
+                .iter()
+                .filter(|folder| mod_root.join(folder).is_dir())
+                .map(|folder| folder.to_string())
+                .collect()
+        } else {
+            for folder in &conf.shared {
+                if !mod_root.join(folder).is_dir() {
+                    warn!("Shared folder '{}' does not exist", folder);
+                }
+            }
+            conf.shared.clone()
+        };
+
+        let versions = versions
+            .into_iter()
+            .map(|version| {
+                let mut folders = Vec::new();
+                if conf.include_root {
+                    folders.push(LoadFolder::plain("/"));
+                }
+                folders.extend(shared.iter().map(|folder| LoadFolder::plain(folder)));
+                if has_version_dirs {
+                    folders.push(LoadFolder::plain(&version));
+                }
+                folders.extend(
+                    conf.conditional
+                        .iter()
+                        .filter(|c| c.versions.is_empty() || c.versions.contains(&version))
+                        .map(|c| LoadFolder {
+                            path: c.folder.replace("{version}", &version),
+                            if_mod_active: c.if_mod_active.clone(),
+                            if_mod_not_active: c.if_mod_not_active.clone(),
+                        }),
+                );
+                (version, folders)
+            })
+            .collect();
+
+        Ok(LoadFolders { versions })
+    }
+
+    /// Reads `LoadFolders.xml` from `mod_root`, if there is one.
+    pub fn load(mod_root: &Path) -> Result<Option<Self>> {
+        let path = mod_root.join(LOAD_FOLDERS_FILE_NAME);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&path)?;
+        Self::from_xml(&content)
+            .map(Some)
+            .map_err(|e| anyhow!("Failed to parse {}: {}", path.display(), e))
+    }
+
+    pub fn from_xml(content: &str) -> Result<Self> {
+        let doc = Document::parse(content)?;
+        let root = doc.root_element();
+        if root.tag_name().name() != ROOT_TAG {
+            bail!(
+                "Expected root element <{}>, found <{}>",
+                ROOT_TAG,
+                root.tag_name().name()
+            );
+        }
+
+        let versions = root
+            .children()
+            .filter(|n| n.is_element())
+            .map(|node| {
+                let tag = node.tag_name().name();
+                let version = tag.strip_prefix('v').unwrap_or(tag).to_string();
+                let folders = node
+                    .children()
+                    .filter(|n| n.is_element() && n.tag_name().name() == "li")
+                    .map(|li| LoadFolder {
+                        path: li.text().unwrap_or("").trim().to_string(),
+                        if_mod_active: li.attribute("IfModActive").map(String::from),
+                        if_mod_not_active: li.attribute("IfModNotActive").map(String::from),
+                    })
+                    .collect();
+                (version, folders)
+            })
+            .collect();
+
+        Ok(LoadFolders { versions })
+    }
+
+    /// The folder loading `relative` for `version`, the deepest one if they
+    /// are nested.
+    pub fn folder_for(&self, version: &str, relative: &Path) -> Option<&LoadFolder> {
+        self.get(version)?
+            .iter()
+            .filter(|f| relative.starts_with(normalize(&f.path)))
+            .max_by_key(|f| normalize(&f.path).components().count())
+    }
+
+    pub fn get(&self, version: &str) -> Option<&[LoadFolder]> {
+        self.versions
+            .iter()
+            .find(|(v, _)| v == version)
+            .map(|(_, folders)| folders.as_slice())
+    }
+
+    pub fn save(&self, mod_root: &Path) -> Result<()> {
+        fs::write(mod_root.join(LOAD_FOLDERS_FILE_NAME), self.to_xml())?;
+        Ok(())
+    }
+
+    pub fn to_xml(&self) -> String {
+        let mut w = XmlWriter::new();
+        w.open(ROOT_TAG);
+        for (version, folders) in &self.versions {
+            let tag = format!("v{}", version);
+            w.open(&tag);
+            for folder in folders {
+                let mut attrs = Vec::new();
+                if let Some(mods) = &folder.if_mod_active {
+                    attrs.push(("IfModActive", mods.as_str()));
+                }
+                if let Some(mods) = &folder.if_mod_not_active {
+                    attrs.push(("IfModNotActive", mods.as_str()));
+                }
+                w.elem_attrs("li", &attrs, &folder.path);
+            }
+            w.close(&tag);
+        }
+        w.close(ROOT_TAG);
+        w.finish()
+    }
+}
+
+/// Decides which project paths RimWorld would load for a single game version.
+#[derive(Debug)]
+pub struct VersionFilter {
+    /// Folders and whether they are loaded, the deepest match wins
+    rules: Vec<(PathBuf, bool)>,
+}
+
+impl VersionFilter {
+    /// Uses `LoadFolders.xml` when present, otherwise RimWorld's defaults of
+    /// the root, `Common` and the closest version directory not newer than
+    /// `version`.
+    pub fn new(mod_root: &Path, version: &str) -> Result<Self> {
+        let Some(wanted) = parse_version(version) else {
+            bail!("Invalid game version '{}', expected e.g. '1.5'", version);
+        };
+        let version_dirs = find_version_dirs(mod_root)?;
+
+        let load_folders = LoadFolders::load(mod_root)?;
+
+        let loaded = match &load_folders {
+            Some(load_folders) => {
+                debug!("Filtering by {}", LOAD_FOLDERS_FILE_NAME);
+                let Some(folders) = load_folders.get(version) else {
+                    bail!("{} has no entry for v{}", LOAD_FOLDERS_FILE_NAME, version);
+                };
+                folders
+                    .iter()
+                    .map(|f| normalize(&f.path))
+                    .collect::<Vec<_>>()
+            },
+            None => {
+                debug!("No {}, using default load folders", LOAD_FOLDERS_FILE_NAME);
+                let mut loaded = vec![PathBuf::new(), PathBuf::from("Common")];
+                if let Some(dir) = version_dirs
+                    .iter()
+                    .rev()
+                    .find(|dir| parse_version(dir).is_some_and(|v| v <= wanted))
+                {
+                    loaded.push(PathBuf::from(dir));
+                }
+                loaded
+            },
+        };
+
+        let mut rules = version_dirs
+            .iter()
+            .map(|dir| (PathBuf::from(dir), false))
+            .collect::<Vec<_>>();
+        if let Some(load_folders) = &load_folders {
+            rules.extend(
+                load_folders
+                    .versions
+                    .iter()
+                    .filter(|(v, _)| v != version)
+                    .flat_map(|(_, folders)| folders)
+                    .map(|f| (normalize(&f.path), false)),
+            );
+        }
+        // Loaded folders override exclusions of the same path
+        rules.retain(|(path, _)| !loaded.contains(path));
+        rules.extend(loaded.into_iter().map(|path| (path, true)));
+        // The root itself is excluded unless loaded explicitly
+        if !rules.iter().any(|(path, _)| path.as_os_str().is_empty()) {
+            rules.push((PathBuf::new(), false));
+        }
+
+        Ok(VersionFilter { rules })
+    }
+
+    pub fn includes(&self, relative: &Path, is_dir: bool) -> bool {
+        // Metadata is read from the mod root regardless of load folders
+        if relative.starts_with("About") || (!is_dir && relative.parent() == Some(Path::new(""))) {
+            return true;
+        }
+        // Keep parents of loaded folders so their structure exists
+        if is_dir
+            && self
+                .rules
+                .iter()
+                .any(|(path, loaded)| *loaded && path.starts_with(relative))
+        {
+            return true;
+        }
+        self.rules
+            .iter()
+            .filter(|(path, _)| relative.starts_with(path))
+            .max_by_key(|(path, _)| path.components().count())
+            .is_some_and(|(_, loaded)| *loaded)
+    }
+}
+
+/// Which game versions load each project file, and under which mod
+/// conditions, to tell files that can never be loaded together apart.
+#[derive(Debug, Default)]
+pub struct LoadScopes {
+    filters: Vec<(String, VersionFilter)>,
+    load_folders: Option<LoadFolders>,
+}
+
+impl LoadScopes {
+    /// Covers the versions in `LoadFolders.xml`, or the version directories
+    /// without one. A mod with neither loads every file together.
+    pub fn new(mod_root: &Path) -> Result<Self> {
+        let load_folders = LoadFolders::load(mod_root)?;
+        let versions = match &load_folders {
+            Some(load_folders) => load_folders
+                .versions
+                .iter()
+                .map(|(version, _)| version.clone())
+                .filter(|version| parse_version(version).is_some())
+                .collect(),
+            None => find_version_dirs(mod_root)?,
+        };
+        let filters = versions
+            .into_iter()
+            .map(|version| {
+                let filter = VersionFilter::new(mod_root, &version)?;
+                Ok((version, filter))
+            })
+            .collect::<Result<_>>()?;
+        Ok(LoadScopes {
+            filters,
+            load_folders,
+        })
+    }
+
+    /// Whether both files can be loaded by the same game version with the
+    /// same mods active.
+    pub fn loaded_together(&self, a: &Path, b: &Path) -> bool {
+        if self.filters.is_empty() {
+            return true;
+        }
+        self.filters.iter().any(|(version, filter)| {
+            filter.includes(a, false) && filter.includes(b, false) && !self.exclusive(version, a, b)
+        })
+    }
+
+    /// Whether every game version loading `file` also loads `other`.
+    pub fn loaded_with(&self, file: &Path, other: &Path) -> bool {
+        self.filters
+            .iter()
+            .all(|(_, filter)| !filter.includes(file, false) || filter.includes(other, false))
+    }
+
+    /// Whether the folders loading `a` and `b` for `version` require a mod to
+    /// be both active and inactive.
+    fn exclusive(&self, version: &str, a: &Path, b: &Path) -> bool {
+        let Some(load_folders) = &self.load_folders else {
+            return false;
+        };
+        let (Some(a), Some(b)) = (
+            load_folders.folder_for(version, a),
+            load_folders.folder_for(version, b),
+        ) else {
+            return false;
+        };
+        // Loaded if any of `active` is, which none of `not_active` may be
+        let excludes = |active: &Option<String>, not_active: &Option<String>| {
+            let (Some(active), Some(not_active)) = (active, not_active) else {
+                return false;
+            };
+            let not_active = package_ids(not_active).collect::<Vec<_>>();
+            package_ids(active).all(|id| not_active.iter().any(|n| n.eq_ignore_ascii_case(id)))
+        };
+        excludes(&a.if_mod_active, &b.if_mod_not_active)
+            || excludes(&b.if_mod_active, &a.if_mod_not_active)
+    }
+}
+
+fn package_ids(list: &str) -> impl Iterator<Item = &str> {
+    list.split(',').map(str::trim).filter(|id| !id.is_empty())
+}
+
+fn normalize(folder: &str) -> PathBuf {
+    PathBuf::from(folder.replace('\\', "/").trim_matches('/'))
+}
+
+/// Top-level directories named like a game version, such as `1.5`, sorted by
+/// version.
+pub fn find_version_dirs(mod_root: &Path) -> Result<Vec<String>> {
+    let mut versions = fs::read_dir(mod_root)?
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_ok_and(|ft| ft.is_dir()))
+        .filter_map(|entry| entry.file_name().to_str().map(String::from))
+        .filter(|name| parse_version(name).is_some())
+        .collect::<Vec<_>>();
+    versions.sort_by_key(|v| parse_version(v));
+    Ok(versions)
+}
+
+/// Parses a `major.minor` game version.
+pub fn parse_version(version: &str) -> Option<(u32, u32)> {
+    let (major, minor) = version.split_once('.')?;
+    Some((major.parse().ok()?, minor.parse().ok()?))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cli::ConditionalFolder;
+
+    #[test]
+    fn test_generate_load_folders() {
+        let root = tempfile::tempdir().unwrap();
+        for dir in ["1.10", "1.6", "Common", "Textures", "1.x"] {
+            fs::create_dir(root.path().join(dir)).unwrap();
+        }
+        let conf = LoadFoldersConf {
+            conditional: vec![ConditionalFolder {
+                folder: "Mods/Biotech/{version}".into(),
+                if_mod_active: Some("Ludeon.RimWorld.Biotech".into()),
+                versions: vec!["1.6".into()],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let folders = LoadFolders::generate(root.path(), &conf, &[]).unwrap();
+        assert_eq!(
+            folders.to_xml(),
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<loadFolders>
+	<v1.6>
+		<li>/</li>
+		<li>Common</li>
+		<li>1.6</li>
+		<li IfModActive="Ludeon.RimWorld.Biotech">Mods/Biotech/1.6</li>
+	</v1.6>
+	<v1.10>
+		<li>/</li>
+		<li>Common</li>
+		<li>1.10</li>
+	</v1.10>
+</loadFolders>
+"#
+        );
+        assert_eq!(LoadFolders::from_xml(&folders.to_xml()).unwrap(), folders);
+    }
+
+    #[test]
+    fn test_version_filter() {
+        let root = tempfile::tempdir().unwrap();
+        for dir in ["1.4", "1.5", "Common"] {
+            fs::create_dir(root.path().join(dir)).unwrap();
+        }
+
+        let filter = VersionFilter::new(root.path(), "1.6").unwrap();
+        assert!(filter.includes(Path::new("Defs/A.xml"), false));
+        assert!(filter.includes(Path::new("Common/Defs/A.xml"), false));
+        assert!(filter.includes(Path::new("1.5/Assemblies/A.dll"), false));
+        assert!(!filter.includes(Path::new("1.4/Assemblies/A.dll"), false));
+
+        fs::write(
+            root.path().join(LOAD_FOLDERS_FILE_NAME),
+            "<loadFolders><v1.4><li>1.4</li></v1.4><v1.5><li>Common</li><li>1.5</li><li>Compat/1.5</li></v1.5></loadFolders>",
+        )
+        .unwrap();
+        let filter = VersionFilter::new(root.path(), "1.5").unwrap();
+        assert!(filter.includes(Path::new("About/About.xml"), false));
+        assert!(filter.includes(Path::new("LoadFolders.xml"), false));
+        assert!(filter.includes(Path::new("Compat"), true));
+        assert!(filter.includes(Path::new("Compat/1.5/Patches/A.xml"), false));
+        assert!(!filter.includes(Path::new("Compat/1.4/Patches/A.xml"), false));
+        assert!(!filter.includes(Path::new("Defs/A.xml"), false));
+        assert!(!filter.includes(Path::new("1.4/Defs/A.xml"), false));
+        assert!(VersionFilter::new(root.path(), "1.6").is_err());
+    }
+
+    #[test]
+    fn test_load_scopes() {
+        let root = tempfile::tempdir().unwrap();
+        let path = Path::new;
+        assert!(
+            LoadScopes::new(root.path())
+                .unwrap()
+                .loaded_together(path("Defs/A.xml"), path("Defs/B.xml"))
+        );
+
+        for dir in ["1.5", "1.6"] {
+            fs::create_dir(root.path().join(dir)).unwrap();
+        }
+        fs::write(
+            root.path().join(LOAD_FOLDERS_FILE_NAME),
+            r#"<loadFolders>
+                <v1.5><li>/</li><li>Compat/1.5</li></v1.5>
+                <v1.6>
+                    <li>/</li>
+                    <li>Compat/1.6</li>
+                    <li IfModActive="Other.Mod">Mods/With</li>
+                    <li IfModNotActive="Other.Mod, Third.Mod">Mods/Without</li>
+                </v1.6>
+            </loadFolders>"#,
+        )
+        .unwrap();
+        let scopes = LoadScopes::new(root.path()).unwrap();
+        let together = |a, b| scopes.loaded_together(path(a), path(b));
+        assert!(!together("Compat/1.5/Defs/A.xml", "Compat/1.6/Defs/A.xml"));
+        assert!(together("Defs/A.xml", "Compat/1.6/Defs/A.xml"));
+        assert!(!together("Mods/With/Defs/A.xml", "Mods/Without/Defs/A.xml"));
+        assert!(together("Mods/With/Defs/A.xml", "Defs/A.xml"));
+
+        assert!(scopes.loaded_with(path("Compat/1.6/Defs/A.xml"), path("Defs/A.xml")));
+        assert!(!scopes.loaded_with(path("Defs/A.xml"), path("Compat/1.6/Defs/A.xml")));
+    }
+}
@@ -1,6 +1,11 @@
+mod about;
 mod cli;
+mod lint;
+mod load_folders;
 mod logger;
 mod util;
+mod vdf;
+mod xml;
 
 use anyhow::Result;
 use clap::Parser;
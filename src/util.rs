@@ -1,11 +1,14 @@
 use std::{
+    fs,
     io::{Write, stdin, stdout},
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
-use anyhow::{Result, anyhow};
+use anyhow::Result;
 use log::{debug, warn};
 
+use crate::vdf;
+
 pub fn get_dir() -> PathBuf {
     dirs::home_dir()
         .expect("Failed to get home directory")
@@ -15,6 +18,7 @@ pub fn get_dir() -> PathBuf {
 pub fn read_steam_install_path() -> Result<Option<PathBuf>> {
     #[cfg(target_os = "windows")]
     {
+        use anyhow::anyhow;
         use winreg::{RegKey, enums::HKEY_LOCAL_MACHINE};
 
         const STEAM_REG_PATH: &str = r"SOFTWARE\WOW6432Node\Valve\Steam";
@@ -48,11 +52,90 @@ pub fn read_steam_install_path() -> Result<Option<PathBuf>> {
 
     #[cfg(not(target_os = "windows"))]
     {
-        debug!("Reading Steam install path is not supported on this OS");
-        Ok(None)
+        debug!("Probing standard Steam install locations");
+        Ok(dirs::home_dir().and_then(|home| probe_steam_root(&home)))
     }
 }
 
+/// Steam install locations relative to the home directory, in probing order.
+#[cfg(not(target_os = "windows"))]
+const STEAM_ROOT_CANDIDATES: &[&str] = &[
+    #[cfg(target_os = "macos")]
+    "Library/Application Support/Steam",
+    ".steam/steam",
+    ".local/share/Steam",
+    ".var/app/com.valvesoftware.Steam/.local/share/Steam",
+    ".var/app/com.valvesoftware.Steam/data/Steam",
+];
+
+/// Returns the first Steam root under `home` that contains a `steamapps`
+/// directory.
+#[cfg(not(target_os = "windows"))]
+fn probe_steam_root(home: &Path) -> Option<PathBuf> {
+    STEAM_ROOT_CANDIDATES
+        .iter()
+        .map(|candidate| home.join(candidate))
+        .find(|path| {
+            let valid = path.join("steamapps").is_dir();
+            debug!(
+                "Steam root candidate {}: {}",
+                path.display(),
+                if valid { "found" } else { "not found" }
+            );
+            valid
+        })
+}
+
+pub const RIMWORLD_APP_ID: &str = "294100";
+
+/// Finds the install directory of `app_id` across every Steam library listed in
+/// `libraryfolders.vdf` under `steam_root`.
+pub fn find_steam_app_dir(steam_root: &Path, app_id: &str) -> Option<PathBuf> {
+    let mut libraries = vec![steam_root.to_path_buf()];
+    for vdf_path in [
+        steam_root.join("steamapps/libraryfolders.vdf"),
+        steam_root.join("config/libraryfolders.vdf"),
+    ] {
+        let Ok(content) = fs::read_to_string(&vdf_path) else {
+            continue;
+        };
+        debug!("Reading Steam libraries from {}", vdf_path.display());
+        match vdf::parse(&content) {
+            Ok(root) => libraries.extend(
+                root.get("libraryfolders")
+                    .and_then(vdf::Value::as_obj)
+                    .into_iter()
+                    .flat_map(|folders| folders.iter())
+                    // Libraries are numbered, other keys hold Steam statistics
+                    .filter(|(key, _)| key.parse::<u32>().is_ok())
+                    .filter_map(|(_, folder)| match folder {
+                        // Old format maps index to path directly
+                        vdf::Value::Str(path) => Some(PathBuf::from(path)),
+                        vdf::Value::Obj(obj) => obj
+                            .get("path")
+                            .and_then(vdf::Value::as_str)
+                            .map(PathBuf::from),
+                    }),
+            ),
+            Err(e) => warn!("Failed to parse {}: {}", vdf_path.display(), e),
+        }
+        break;
+    }
+
+    libraries.into_iter().find_map(|library| {
+        let manifest_path = library.join(format!("steamapps/appmanifest_{app_id}.acf"));
+        let content = fs::read_to_string(&manifest_path).ok()?;
+        debug!("Found app manifest at {}", manifest_path.display());
+        let install_dir = vdf::parse(&content)
+            .inspect_err(|e| warn!("Failed to parse {}: {}", manifest_path.display(), e))
+            .ok()?
+            .get_path(&["AppState", "installdir"])?
+            .as_str()?
+            .to_string();
+        Some(library.join("steamapps/common").join(install_dir))
+    })
+}
+
 pub fn decode_out(bytes: &[u8]) -> String {
     #[cfg(target_os = "windows")]
     {
@@ -71,6 +154,42 @@ pub fn decode_out(bytes: &[u8]) -> String {
     }
 }
 
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64;
+    let mut unit = "B";
+    for next in UNITS {
+        if size < 1024.0 {
+            break;
+        }
+        size /= 1024.0;
+        unit = next;
+    }
+    format!("{:.1} {}", size, unit)
+}
+
+/// Asks for a line of input, falling back to `default` when left empty.
+pub fn prompt(msg: &str, default: &str) -> String {
+    if default.is_empty() {
+        print!("{}: ", msg);
+    } else {
+        print!("{} [{}]: ", msg, default);
+    }
+    stdout().flush().expect("Failed to flush stdout");
+
+    let mut input = String::new();
+    stdin().read_line(&mut input).expect("Failed to read line");
+
+    match input.trim() {
+        "" => default.to_string(),
+        input => input.to_string(),
+    }
+}
+
 pub fn confirm(msg: &str) -> bool {
     print!("{} (y/n): ", msg);
     stdout().flush().expect("Failed to flush stdout");
@@ -91,4 +210,71 @@ mod test {
         assert!(path.is_ok(), "Should be able to read Steam install path");
         dbg!("Steam install path: {:?}", path.unwrap());
     }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn test_probe_steam_root() {
+        let home = tempfile::tempdir().unwrap();
+        assert_eq!(probe_steam_root(home.path()), None);
+
+        let flatpak = home
+            .path()
+            .join(".var/app/com.valvesoftware.Steam/.local/share/Steam");
+        fs::create_dir_all(flatpak.join("steamapps")).unwrap();
+        assert_eq!(probe_steam_root(home.path()), Some(flatpak));
+
+        let native = home.path().join(".local/share/Steam");
+        fs::create_dir_all(native.join("steamapps")).unwrap();
+        assert_eq!(probe_steam_root(home.path()), Some(native));
+    }
+
+    #[test]
+    fn test_find_steam_app_dir() {
+        let steam = tempfile::tempdir().unwrap();
+        let library = tempfile::tempdir().unwrap();
+        fs::create_dir_all(steam.path().join("steamapps")).unwrap();
+        fs::create_dir_all(library.path().join("steamapps")).unwrap();
+        assert_eq!(find_steam_app_dir(steam.path(), RIMWORLD_APP_ID), None);
+
+        fs::write(
+            steam.path().join("steamapps/libraryfolders.vdf"),
+            format!(
+                r#""libraryfolders" {{ "0" {{ "path" "{}" }} "1" {{ "path" "{}" }} }}"#,
+                steam.path().display().to_string().replace('\\', r"\\"),
+                library.path().display().to_string().replace('\\', r"\\"),
+            ),
+        )
+        .unwrap();
+        fs::write(
+            library.path().join("steamapps/appmanifest_294100.acf"),
+            r#""AppState" { "appid" "294100" "installdir" "RimWorld" }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            find_steam_app_dir(steam.path(), RIMWORLD_APP_ID),
+            Some(library.path().join("steamapps/common/RimWorld"))
+        );
+
+        // The old format mixes numbered paths with other values
+        let other = tempfile::tempdir().unwrap();
+        fs::create_dir_all(other.path().join("steamapps")).unwrap();
+        fs::write(
+            other.path().join("steamapps/appmanifest_294100.acf"),
+            r#""AppState" { "appid" "294100" "installdir" "Other" }"#,
+        )
+        .unwrap();
+        fs::write(
+            steam.path().join("steamapps/libraryfolders.vdf"),
+            format!(
+                r#""LibraryFolders" {{ "TimeNextStatsReport" "1700000000" "ContentStatsID" "{}" "1" "{}" }}"#,
+                other.path().display().to_string().replace('\\', r"\\"),
+                library.path().display().to_string().replace('\\', r"\\"),
+            ),
+        )
+        .unwrap();
+        assert_eq!(
+            find_steam_app_dir(steam.path(), RIMWORLD_APP_ID),
+            Some(library.path().join("steamapps/common/RimWorld"))
+        );
+    }
 }
@@ -0,0 +1,288 @@
+use std::{iter::Peekable, str::Chars};
+
+use anyhow::{Result, anyhow, bail};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    Str(String),
+    Obj(Object),
+}
+
+/// Ordered key-value pairs; keys may repeat and are matched case-insensitively.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Object(pub Vec<(String, Value)>);
+
+impl Value {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::Str(s) => Some(s),
+            Value::Obj(_) => None,
+        }
+    }
+
+    pub fn as_obj(&self) -> Option<&Object> {
+        match self {
+            Value::Obj(obj) => Some(obj),
+            Value::Str(_) => None,
+        }
+    }
+}
+
+impl Object {
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.0
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v)
+    }
+
+    /// Follows a path of nested object keys.
+    pub fn get_path(&self, path: &[&str]) -> Option<&Value> {
+        let (last, parents) = path.split_last()?;
+        let mut obj = self;
+        for key in parents {
+            obj = obj.get(key)?.as_obj()?;
+        }
+        obj.get(last)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.0.iter().map(|(k, v)| (k.as_str(), v))
+    }
+
+    /// Serializes into the tab-indented layout Steam writes itself.
+    pub fn to_vdf(&self) -> String {
+        let mut out = String::new();
+        self.write(&mut out, 0);
+        out
+    }
+
+    fn write(&self, out: &mut String, depth: usize) {
+        let indent = "\t".repeat(depth);
+        for (key, value) in &self.0 {
+            match value {
+                Value::Str(s) => out.push_str(&format!(
+                    "{}\"{}\"\t\t\"{}\"\n",
+                    indent,
+                    escape(key),
+                    escape(s)
+                )),
+                Value::Obj(obj) => {
+                    out.push_str(&format!("{}\"{}\"\n{}{{\n", indent, escape(key), indent));
+                    obj.write(out, depth + 1);
+                    out.push_str(&format!("{}}}\n", indent));
+                },
+            }
+        }
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t")
+}
+
+/// Parses Valve's KeyValues (VDF) text format, as used by
+/// `libraryfolders.vdf` and `appmanifest_*.acf`.
+pub fn parse(input: &str) -> Result<Object> {
+    let mut parser = Parser {
+        chars: input.trim_start_matches('\u{feff}').chars().peekable(),
+        line: 1,
+    };
+    let obj = parser.object()?;
+    match parser.token()? {
+        None => Ok(obj),
+        Some(_) => Err(anyhow!("Unexpected '}}' at line {}", parser.line)),
+    }
+}
+
+enum Token {
+    Str(String),
+    Open,
+    Close,
+}
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+}
+
+impl Parser<'_> {
+    /// Reads pairs until a closing brace or the end of input, leaving the
+    /// closing brace to the caller.
+    fn object(&mut self) -> Result<Object> {
+        let mut obj = Object::default();
+        loop {
+            self.skip_trivia();
+            match self.chars.peek() {
+                None | Some('}') => return Ok(obj),
+                _ => {},
+            }
+
+            let key = match self.token()? {
+                Some(Token::Str(key)) => key,
+                _ => bail!("Expected key at line {}", self.line),
+            };
+            let value = match self.token()? {
+                Some(Token::Str(value)) => Value::Str(value),
+                Some(Token::Open) => {
+                    let inner = self.object()?;
+                    match self.token()? {
+                        Some(Token::Close) => Value::Obj(inner),
+                        _ => bail!("Unclosed object '{}' at line {}", key, self.line),
+                    }
+                },
+                _ => bail!("Expected value for '{}' at line {}", key, self.line),
+            };
+            self.skip_conditional();
+            obj.0.push((key, value));
+        }
+    }
+
+    fn token(&mut self) -> Result<Option<Token>> {
+        self.skip_trivia();
+        let Some(c) = self.chars.next() else {
+            return Ok(None);
+        };
+        Ok(Some(match c {
+            '{' => Token::Open,
+            '}' => Token::Close,
+            '"' => Token::Str(self.quoted()?),
+            c => {
+                let mut s = String::from(c);
+                while let Some(&c) = self.chars.peek() {
+                    if c.is_whitespace() || matches!(c, '"' | '{' | '}') {
+                        break;
+                    }
+                    s.push(c);
+                    self.chars.next();
+                }
+                Token::Str(s)
+            },
+        }))
+    }
+
+    fn quoted(&mut self) -> Result<String> {
+        let start = self.line;
+        let mut s = String::new();
+        loop {
+            match self.chars.next() {
+                None => bail!("Unterminated string starting at line {}", start),
+                Some('"') => return Ok(s),
+                Some('\\') => match self.chars.next() {
+                    Some('n') => s.push('\n'),
+                    Some('t') => s.push('\t'),
+                    Some(c) => s.push(c),
+                    None => bail!("Unterminated string starting at line {}", start),
+                },
+                Some(c) => {
+                    if c == '\n' {
+                        self.line += 1;
+                    }
+                    s.push(c);
+                },
+            }
+        }
+    }
+
+    fn skip_trivia(&mut self) {
+        while let Some(&c) = self.chars.peek() {
+            if c == '\n' {
+                self.line += 1;
+                self.chars.next();
+            } else if c.is_whitespace() {
+                self.chars.next();
+            } else if c == '/' {
+                // Line comment, a lone slash is treated the same way
+                while self.chars.next_if(|&c| c != '\n').is_some() {}
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Skips platform conditionals such as `[$WIN32]` after a value.
+    fn skip_conditional(&mut self) {
+        while let Some(&c) = self.chars.peek() {
+            if c == '\n' || !c.is_whitespace() {
+                break;
+            }
+            self.chars.next();
+        }
+        if self.chars.next_if_eq(&'[').is_some() {
+            while self.chars.next_if(|&c| c != ']' && c != '\n').is_some() {}
+            self.chars.next_if_eq(&']');
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_library_folders() {
+        let obj = parse(
+            r#"
+"libraryfolders"
+{
+	"0"
+	{
+		"path"		"C:\\Program Files (x86)\\Steam"
+		"apps"
+		{
+			"228980"		"123"
+		}
+	}
+	// Second library
+	"1"
+	{
+		"path"		"D:\\SteamLibrary"
+		"apps" { "294100" "456" }
+		"osx" "1" [$OSX]
+	}
+}
+"#,
+        )
+        .unwrap();
+
+        let folders = obj.get("LibraryFolders").unwrap().as_obj().unwrap();
+        assert_eq!(folders.iter().count(), 2);
+        assert_eq!(
+            obj.get_path(&["libraryfolders", "1", "path"])
+                .and_then(Value::as_str),
+            Some(r"D:\SteamLibrary")
+        );
+        assert!(
+            obj.get_path(&["libraryfolders", "1", "apps", "294100"])
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn test_write_round_trip() {
+        let obj = Object(vec![(
+            "workshopitem".into(),
+            Value::Obj(Object(vec![
+                ("appid".into(), Value::Str("294100".into())),
+                ("contentfolder".into(), Value::Str(r"C:\Mods\My Mod".into())),
+                (
+                    "changenote".into(),
+                    Value::Str("Fixed \"quotes\"\n\tand tabs".into()),
+                ),
+            ])),
+        )]);
+        let text = obj.to_vdf();
+        assert!(text.starts_with("\"workshopitem\"\n{\n\t\"appid\"\t\t\"294100\"\n"));
+        assert_eq!(parse(&text).unwrap(), obj);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse(r#""a" { "b" "c" "#).is_err());
+        assert!(parse(r#""a" "unterminated"#).is_err());
+        assert!(parse(r#""a" "b" }"#).is_err());
+    }
+}
@@ -0,0 +1,119 @@
+use std::fmt::Write;
+
+use roxmltree::Node;
+
+pub const XML_DECLARATION: &str = r#"<?xml version="1.0" encoding="utf-8"?>"#;
+
+/// Builds tab-indented XML documents in the layout RimWorld itself uses.
+pub struct XmlWriter {
+    buf: String,
+    depth: usize,
+}
+
+impl Default for XmlWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl XmlWriter {
+    pub fn new() -> Self {
+        XmlWriter {
+            buf: format!("{}\n", XML_DECLARATION),
+            depth: 0,
+        }
+    }
+
+    pub fn open(&mut self, tag: &str) -> &mut Self {
+        self.indent();
+        let _ = writeln!(self.buf, "<{}>", tag);
+        self.depth += 1;
+        self
+    }
+
+    pub fn close(&mut self, tag: &str) -> &mut Self {
+        self.depth -= 1;
+        self.indent();
+        let _ = writeln!(self.buf, "</{}>", tag);
+        self
+    }
+
+    pub fn elem(&mut self, tag: &str, text: &str) -> &mut Self {
+        self.elem_attrs(tag, &[], text)
+    }
+
+    pub fn elem_attrs(&mut self, tag: &str, attrs: &[(&str, &str)], text: &str) -> &mut Self {
+        self.indent();
+        let _ = write!(self.buf, "<{}", tag);
+        for (key, value) in attrs {
+            let _ = write!(self.buf, " {}=\"{}\"", key, escape(value));
+        }
+        let _ = writeln!(self.buf, ">{}</{}>", escape(text), tag);
+        self
+    }
+
+    /// Writes `text` as an element only when it is present and not empty.
+    pub fn elem_opt(&mut self, tag: &str, text: Option<&str>) -> &mut Self {
+        match text {
+            Some(text) if !text.is_empty() => self.elem(tag, text),
+            _ => self,
+        }
+    }
+
+    /// Writes a `<li>` list, skipping the element entirely when empty.
+    pub fn list<S: AsRef<str>>(&mut self, tag: &str, items: &[S]) -> &mut Self {
+        if items.is_empty() {
+            return self;
+        }
+        self.open(tag);
+        for item in items {
+            self.elem("li", item.as_ref());
+        }
+        self.close(tag)
+    }
+
+    pub fn finish(self) -> String {
+        self.buf
+    }
+
+    fn indent(&mut self) {
+        self.buf.extend(std::iter::repeat_n('\t', self.depth));
+    }
+}
+
+pub fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Finds the first child element of `node` named `tag`.
+pub fn child<'a, 'input>(node: Node<'a, 'input>, tag: &str) -> Option<Node<'a, 'input>> {
+    node.children()
+        .find(|n| n.is_element() && n.tag_name().name() == tag)
+}
+
+/// Trimmed text of the first child element named `tag`.
+pub fn child_text(node: Node, tag: &str) -> Option<String> {
+    child(node, tag).map(|n| n.text().unwrap_or("").trim().to_string())
+}
+
+/// Trimmed texts of the `<li>` entries under the first child named `tag`.
+pub fn child_list(node: Node, tag: &str) -> Vec<String> {
+    child(node, tag)
+        .map(|list| {
+            list.children()
+                .filter(|n| n.is_element() && n.tag_name().name() == "li")
+                .map(|n| n.text().unwrap_or("").trim().to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}